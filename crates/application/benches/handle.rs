@@ -0,0 +1,63 @@
+//! Measures the throughput of `GlobalInputState::handle` for a typing-like stream of events,
+//! including the repeated presses sent while keys are held.
+//!
+//! Run with `cargo bench -p application --bench handle`. To compare a change against what it replaces,
+//! run `cargo bench -p application --bench handle -- --save-baseline before` without the change,
+//! then `cargo bench -p application --bench handle -- --baseline before` with it.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+// The application is a binary crate, so its modules are compiled into the benchmark directly.
+// Only the input handling is used, so the rest of the application is unused here.
+#[allow(dead_code, unused_imports, unused_attributes)]
+#[path = "../src/application.rs"]
+mod application;
+
+fn typing_events() -> Vec<rdev::Event> {
+	let typed = [
+		rdev::Key::KeyF,
+		rdev::Key::KeyJ,
+		rdev::Key::ShiftLeft,
+		rdev::Key::KeyD,
+		rdev::Key::KeyK,
+		rdev::Key::Space,
+		rdev::Key::KeyA,
+		rdev::Key::SemiColon,
+	];
+	let mut events = Vec::with_capacity(typed.len() * 5);
+	for key in typed {
+		events.push(rdev::EventType::KeyPress(key));
+		// repeats while the key is held
+		for _ in 0..3 {
+			events.push(rdev::EventType::KeyPress(key));
+		}
+		events.push(rdev::EventType::KeyRelease(key));
+	}
+	let events = events.into_iter().map(|event_type| rdev::Event {
+		time: std::time::SystemTime::now(),
+		name: None,
+		event_type,
+	});
+	events.collect()
+}
+
+fn handle(c: &mut Criterion) {
+	let config = application::parse_config_kdl(include_str!("typing.kdl")).expect("failed to parse config");
+	let input = application::GlobalInputState::default();
+	input.update_bindings(&config);
+	let events = typing_events();
+
+	let mut group = c.benchmark_group("handle");
+	group.throughput(Throughput::Elements(events.len() as u64));
+	group.bench_function("typing", |b| {
+		b.iter(|| {
+			for event in &events {
+				input.handle(std::hint::black_box(event));
+			}
+		})
+	});
+	group.finish();
+}
+
+criterion_group!(benches, handle);
+criterion_main!(benches);
@@ -3,17 +3,29 @@
 
 use multimap::MultiMap;
 use std::{
-	collections::{BTreeSet, HashMap, HashSet},
-	sync::{Arc, RwLock},
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+	sync::{
+		atomic::{AtomicBool, AtomicU64, Ordering},
+		Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard,
+	},
 };
 use tauri::{CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu, SystemTraySubmenu};
 use tauri_plugin_log::LogTarget;
-use tauri_plugin_positioner::WindowExt;
 
 static TRAY_CONFIG_IMPORT: (&'static str, &'static str) = ("config:import", "Import Config");
 static TRAY_CONFIG_EXPORT: (&'static str, &'static str) = ("config:export", "Export Config");
 static TRAY_CONFIG_OPEN_DIR: (&'static str, &'static str) = ("open_config_dir", "Open Config Folder");
 static TRAY_CONFIG_RELOAD: (&'static str, &'static str) = ("load_config", "Reload Config");
+static TRAY_LAYOUT_EXPORT_SVG: (&'static str, &'static str) = ("layout:export_svg", "Copy Layout SVG");
+static TRAY_FLAG_DESIGN_GRID: (&'static str, &'static str) = ("flag:design_grid", "Design Grid");
+static TRAY_FLAG_ALL_LAYERS: (&'static str, &'static str) = ("flag:all_layers", "Show All Layers");
+static TRAY_FREEZE: (&'static str, &'static str) = ("freeze", "Freeze Overlay");
+static TRAY_NEXT_MONITOR: (&'static str, &'static str) = ("window:next_monitor", "Move to Next Monitor");
+static TRAY_COPY_GEOMETRY: (&'static str, &'static str) = ("window:copy_geometry", "Copy Window Geometry");
+static TRAY_SCREENSHOT: (&'static str, &'static str) = ("window:screenshot", "Screenshot Overlay");
+static TRAY_MIRROR_WINDOW: (&'static str, &'static str) = ("window:mirror", "Open Mirror Window");
+static TRAY_COMBO_STATS: (&'static str, &'static str) = ("combo_stats:export", "Copy Combo Stats CSV");
+static TRAY_RESET_PROFILE: (&'static str, &'static str) = ("reset_active_profile", "Reset Active Profile");
 
 static MENU_TOGGLE_ID: &'static str = "toggle";
 static MENU_TOGGLE_HIDE: &'static str = "Hide";
@@ -22,8 +34,23 @@ static EVENT_TOGGLE_WINDOW_VISIBILITY: &'static str = "toggle_window_visibility"
 
 static MENU_QUIT: (&'static str, &'static str) = ("quit", "Quit");
 
+// how many key events can wait to be processed before new events are dropped (instead of stalling the os input hook)
+static INPUT_QUEUE_CAPACITY: usize = 256;
+// how long a switch pressed by the `test_switch` command is held before it is released
+static TEST_SWITCH_DURATION: std::time::Duration = std::time::Duration::from_millis(300);
+// how long after clicking the tray item the overlay is captured, once the tray menu has closed
+static SCREENSHOT_DELAY: std::time::Duration = std::time::Duration::from_millis(250);
+
+mod args;
+pub use args::*;
 mod config;
 pub use config::*;
+mod config_watcher;
+pub use config_watcher::*;
+mod layout_svg;
+pub use layout_svg::*;
+mod state_server;
+pub use state_server::*;
 
 trait ManagerExt<R: tauri::Runtime> {
 	fn emit_and_trigger<S: serde::Serialize + Clone>(&self, event: &str, payload: S) -> tauri::Result<()>;
@@ -39,8 +66,38 @@ where
 	}
 }
 
+#[derive(Default)]
+struct DisplayFlagsMutex(Mutex<shared::DisplayFlags>);
+impl DisplayFlagsMutex {
+	fn get(&self) -> shared::DisplayFlags {
+		*self.0.lock().unwrap()
+	}
+
+	fn update(&self, update: impl FnOnce(&mut shared::DisplayFlags)) -> shared::DisplayFlags {
+		let mut flags = self.0.lock().unwrap();
+		update(&mut *flags);
+		*flags
+	}
+}
+
+#[derive(Default)]
+struct ConfigErrorMutex(Mutex<Option<String>>);
+impl ConfigErrorMutex {
+	fn get(&self) -> Option<String> {
+		self.0.lock().unwrap().clone()
+	}
+
+	fn set(&self, error: Option<String>) {
+		*self.0.lock().unwrap() = error;
+	}
+}
+
 #[derive(Clone, Default)]
-struct GlobalInputState(Arc<RwLock<InputState>>);
+pub(crate) struct GlobalInputState {
+	state: Arc<RwLock<InputState>>,
+	// while frozen, input is ignored so the overlay keeps showing what it last showed
+	frozen: Arc<AtomicBool>,
+}
 #[derive(Default)]
 struct InputState {
 	app: Option<tauri::AppHandle<tauri::Wry>>,
@@ -52,10 +109,105 @@ struct InputState {
 
 	pressed_keys: HashSet<rdev::Key>,
 	pressed_hotkeys: HashSet<HotKey>,
+	// the alias which bindings use for each key code, to name pressed keys the way the config does
+	key_aliases: HashMap<rdev::Key, shared::KeyAlias>,
+
+	// bindings whose input is multiple non-modifier keys, triggered while all of the keys are held
+	chords: Vec<(HashSet<rdev::Key>, InputBinding)>,
+	// indices into `chords` which are currently held
+	pressed_chords: HashSet<usize>,
+	// milliseconds since `INPUT_EPOCH` that the most recent key event was received (0 if there hasn't been one),
+	// to detect keys which are stuck because their release was missed. Atomic so repeated presses only need the read lock.
+	last_event_ms: AtomicU64,
+	// held modifiers and toggled locks. The os lock state can't be queried, so locks are assumed off at startup.
+	modifiers: shared::ModifierState,
+
+	// switches which are being held to activate, with the generation of the hold which started it
+	pending_holds: HashMap<Arc<String>, u64>,
+	// incremented for each hold, so a hold which completes after being released and held again is ignored
+	hold_generation: u64,
 
 	default_layer: String,
 	active_layers: HashSet<String>,
 	active_switches: BTreeSet<String>,
+	// layers which suppress the default layer while they are active
+	default_suppressors: HashSet<String>,
+
+	// the switches linked to each combo, whose own bindings are locked out for `combo_lockout` after the combo changes
+	combo_members: HashMap<String, Vec<String>>,
+	combo_lockout: Option<std::time::Duration>,
+	// how long without key events before held keys are released as stuck, if they ever are
+	stuck_input_timeout: Option<std::time::Duration>,
+	switch_lockouts: HashMap<String, std::time::Instant>,
+
+	// hotkeys which step through the layer order, and the layer most recently stepped to (if any)
+	layer_step_hotkeys: HashMap<HotKey, LayerStep>,
+	stepped_layer: Option<String>,
+
+	// how often each combo was attempted and triggered this session, for chord practice
+	combo_stats: BTreeMap<String, ComboStats>,
+}
+
+/// How often a combo was attempted and how often it triggered, accumulated over the session.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize)]
+struct ComboStats {
+	// chords are attempted when their first key is pressed, other combos when their input is pressed
+	attempts: u32,
+	successes: u32,
+	// attempts released before the combo was held for long enough
+	timed_out: u32,
+}
+
+impl ComboStats {
+	/// The stats of the combo which the binding triggers, or None if the binding is for a switch.
+	fn of<'a>(stats: &'a mut BTreeMap<String, ComboStats>, binding: &InputBinding) -> Option<&'a mut ComboStats> {
+		// only combos are bound without a slot
+		if binding.slot.is_some() {
+			return None;
+		}
+		Some(stats.entry((*binding.switch_id).clone()).or_default())
+	}
+}
+
+/// Formats combo stats as csv, with a row per combo.
+fn combo_stats_csv(stats: &BTreeMap<String, ComboStats>) -> String {
+	let mut csv = String::from("combo,attempts,successes,timed_out,accuracy\n");
+	for (combo_id, stats) in stats {
+		let accuracy = match stats.attempts {
+			0 => 0.0,
+			attempts => stats.successes as f64 / attempts as f64,
+		};
+		let combo_id = match combo_id.contains([',', '"', '\n']) {
+			true => format!("\"{}\"", combo_id.replace('"', "\"\"")),
+			false => combo_id.clone(),
+		};
+		let (attempts, successes, timed_out) = (stats.attempts, stats.successes, stats.timed_out);
+		csv += &format!("{combo_id},{attempts},{successes},{timed_out},{accuracy:.3}\n");
+	}
+	csv
+}
+
+/// The keys and hotkeys the input engine currently considers pressed, for external tooling and debugging.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+struct PressedInput {
+	// keys named by the alias the config binds them with, or by their key code if no binding uses them
+	pressed_keys: Vec<String>,
+	pressed_hotkeys: Vec<String>,
+}
+
+/// What caused a binding to change, for tracing input.
+enum InputTrigger<'a> {
+	HotKey(&'a HotKey),
+	Chord(&'a HashSet<rdev::Key>),
+}
+
+impl std::fmt::Display for InputTrigger<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::HotKey(hotkey) => write!(f, "hotkey {hotkey}"),
+			Self::Chord(keys) => write!(f, "chord {keys:?}"),
+		}
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -65,18 +217,213 @@ struct InputBinding {
 	slot: Option<shared::SwitchSlot>,
 	key: shared::KeySet,
 	target_layer: Option<Arc<String>>,
+	on_press: Option<Arc<String>>,
+	// how long the input must be held before the binding triggers
+	hold: Option<std::time::Duration>,
+	// bindings triggered by the same key event are emitted highest priority first
+	priority: i64,
+	// the keyboard this binding is limited to, if any
+	device: Option<Arc<String>>,
+}
+
+impl InputBinding {
+	/// Returns true if a key from the device can trigger this binding.
+	/// Keys from an unknown device trigger every binding, since most platforms can't tell keyboards apart.
+	fn matches_device(&self, device: Option<&str>) -> bool {
+		match (&self.device, device) {
+			(Some(scoped), Some(device)) => scoped.as_str() == device,
+			_ => true,
+		}
+	}
+
+	/// Returns true if both bindings can trigger while the same layer is active.
+	/// Bindings without layers trigger from any layer.
+	fn shares_layer(&self, other: &InputBinding) -> bool {
+		if self.layer_id.is_empty() || other.layer_id.is_empty() {
+			return true;
+		}
+		!self.layer_id.is_disjoint(&other.layer_id)
+	}
+}
+
+/// The name of the keyboard which sent a key event, for bindings limited to a `device`.
+/// rdev doesn't report the source device of events on any platform yet, so this is always unknown
+/// and device-scoped bindings are triggered by every keyboard.
+fn event_device(_event: &rdev::Event) -> Option<&str> {
+	None
 }
 
+static INPUT_EPOCH: once_cell::sync::Lazy<std::time::Instant> = once_cell::sync::Lazy::new(std::time::Instant::now);
+
 impl InputState {
-	fn can_trigger(&self, binding: &InputBinding) -> bool {
+	fn mark_event(&self) {
+		let now_ms = (INPUT_EPOCH.elapsed().as_millis() as u64).max(1);
+		self.last_event_ms.store(now_ms, Ordering::Relaxed);
+	}
+
+	/// How long it has been since the most recent key event, if there has been one.
+	fn idle_duration(&self) -> Option<std::time::Duration> {
+		let last_event_ms = self.last_event_ms.load(Ordering::Relaxed);
+		if last_event_ms == 0 {
+			return None;
+		}
+		let last_event = std::time::Duration::from_millis(last_event_ms);
+		Some(INPUT_EPOCH.elapsed().saturating_sub(last_event))
+	}
+
+	fn toggle_lock(&mut self, key: rdev::Key) {
+		match key {
+			rdev::Key::CapsLock => self.modifiers.caps_lock = !self.modifiers.caps_lock,
+			rdev::Key::NumLock => self.modifiers.num_lock = !self.modifiers.num_lock,
+			rdev::Key::ScrollLock => self.modifiers.scroll_lock = !self.modifiers.scroll_lock,
+			_ => {}
+		}
+	}
+
+	/// Updates the held modifiers from the pressed keys, emitting the modifier state if it differs from `previous`.
+	fn update_modifiers(&mut self, previous: shared::ModifierState) {
+		let is_held = |keys: &[rdev::Key]| keys.iter().any(|key| self.pressed_keys.contains(key));
+		let modifiers = shared::ModifierState {
+			shift: is_held(&[rdev::Key::ShiftLeft, rdev::Key::ShiftRight]),
+			ctrl: is_held(&[rdev::Key::ControlLeft, rdev::Key::ControlRight]),
+			alt: is_held(&[rdev::Key::Alt, rdev::Key::AltGr]),
+			meta: is_held(&[rdev::Key::MetaLeft, rdev::Key::MetaRight]),
+			..self.modifiers
+		};
+		self.modifiers = modifiers;
+		if modifiers == previous {
+			return;
+		}
+		if let Some(app) = &self.app {
+			let _ = app.emit_all("modifiers", modifiers);
+		}
+	}
+
+	/// Releases all held keys, switches, and non-default layers.
+	fn release_all(&mut self) {
+		self.pressed_keys.clear();
+		self.pressed_hotkeys.clear();
+		self.pressed_chords.clear();
+		let previous_modifiers = self.modifiers;
+		self.update_modifiers(previous_modifiers);
+
+		let mut updates = Vec::new();
+		for (switch_id, _generation) in self.pending_holds.drain() {
+			updates.push(shared::InputUpdate::HoldCancelled((*switch_id).clone()));
+		}
+		for switch_id in std::mem::take(&mut self.active_switches) {
+			updates.push(shared::InputUpdate::SwitchReleased(switch_id));
+		}
+		let default_layer = self.default_layer.clone();
+		self.active_layers.retain(|layer| {
+			if *layer == default_layer {
+				return true;
+			}
+			updates.push(shared::InputUpdate::LayerDeactivate(layer.clone()));
+			false
+		});
+		if let (Some(app), false) = (&self.app, updates.is_empty()) {
+			let _ = app.emit_all("input", shared::InputUpdate::Batch(updates));
+		}
+	}
+
+	/// Applies updates to the active layers and switches, then emits them and any effects to the frontend.
+	fn commit_updates(&mut self, updates: Vec<shared::InputUpdate>, effects: Vec<String>) {
+		if updates.is_empty() {
+			return;
+		}
+
+		for update in &updates {
+			match update {
+				shared::InputUpdate::LayerActivate(layer) => {
+					self.active_layers.insert(layer.clone());
+				}
+				shared::InputUpdate::LayerDeactivate(layer) => {
+					self.active_layers.remove(layer);
+				}
+				shared::InputUpdate::SwitchPressed(switch_id, _slot) => {
+					self.active_switches.insert(switch_id.clone());
+				}
+				shared::InputUpdate::SwitchReleased(switch_id) => {
+					self.active_switches.remove(switch_id);
+				}
+				shared::InputUpdate::HoldStarted(_) | shared::InputUpdate::HoldCancelled(_) => {}
+				shared::InputUpdate::Batch(_) => {}
+			}
+		}
+
+		// Emit all of the updates for this event at once to reduce the traffic across the event bridge
+		if let Some(app) = &self.app {
+			let _ = app.emit_all("input", shared::InputUpdate::Batch(updates));
+			for effect in effects {
+				let _ = app.emit_all("effect", effect);
+			}
+		}
+	}
+
+	/// Pairs of bindings which are triggered by the same hotkey on a shared layer, so both are pressed at once.
+	/// This is usually an accidental double binding. Bindings with different priorities are ordered on purpose
+	/// (such as a layer and a switch bound to the same key), so they aren't reported.
+	fn overlapping_hotkeys(&self) -> Vec<(HotKey, &InputBinding, &InputBinding)> {
+		let mut overlaps = Vec::new();
+		for (hotkey, bindings) in self.hotkey_bindings.iter_all() {
+			for (idx, binding) in bindings.iter().enumerate() {
+				for other in &bindings[idx + 1..] {
+					if binding.priority == other.priority && binding.shares_layer(other) {
+						overlaps.push((*hotkey, binding, other));
+					}
+				}
+			}
+		}
+		overlaps
+	}
+
+	/// Locks out the bindings of the switches linked to a combo until the layout's combo lockout has passed.
+	fn lock_out_combo_members(&mut self, combo_id: &String) {
+		let (Some(lockout), Some(members)) = (self.combo_lockout, self.combo_members.get(combo_id)) else {
+			return;
+		};
+		let until = std::time::Instant::now() + lockout;
+		for switch_id in members {
+			self.switch_lockouts.insert(switch_id.clone(), until);
+		}
+	}
+
+	fn is_locked_out(&self, switch_id: &String) -> bool {
+		let until = self.switch_lockouts.get(switch_id);
+		until.is_some_and(|until| *until > std::time::Instant::now())
+	}
+
+	/// Returns true if the layer is active, treating the default layer as inactive while a layer suppresses it.
+	fn is_layer_active(&self, layer_id: &String) -> bool {
+		shared::is_layer_active(
+			layer_id,
+			&self.default_layer,
+			&self.active_layers,
+			&self.default_suppressors,
+		)
+	}
+
+	/// Returns the active layer which the binding triggers from,
+	/// or None if none of its layers are active or a higher priority layer binds the same switch.
+	fn triggering_layer(&self, binding: &InputBinding) -> Option<&String> {
+		// switches linked to a combo which was just pressed or released can't trigger on their own
+		if binding.slot.is_some() && self.is_locked_out(&binding.switch_id) {
+			return None;
+		}
+		// bindings without layers (combos which aren't limited to specific layers) trigger from any layer
+		if binding.layer_id.is_empty() {
+			let mut active_layers = self.layer_order.iter().rev();
+			return active_layers.find(|layer_id| self.is_layer_active(layer_id));
+		}
 		for layer_id in self.layer_order.iter().rev() {
 			// The layer being scanned is not active
-			if !self.active_layers.contains(layer_id) {
+			if !self.is_layer_active(layer_id) {
 				continue;
 			}
 			// We found our layer, so it must be able to trigger
 			if binding.layer_id.contains(layer_id) {
-				return true;
+				return Some(layer_id);
 			}
 			// This is some layer with higher priority than the binding, so see if this layer blocks it
 			let Some(bound_switches) = self.layer_switches.get(layer_id) else {
@@ -84,22 +431,74 @@ impl InputState {
 			};
 			if bound_switches.contains(&*binding.switch_id) {
 				// something else has the switch bound
-				return false;
+				return None;
 			}
 		}
-		false
+		None
 	}
 }
 
 impl GlobalInputState {
+	/// Opens the input state for reading, recovering a poisoned lock the same as `write`.
+	fn read(&self) -> RwLockReadGuard<'_, InputState> {
+		self.state.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+
+	/// Opens the input state for writing.
+	/// If a panic poisoned the lock, the state is recovered as-is so input capture can continue for the session.
+	fn write(&self) -> RwLockWriteGuard<'_, InputState> {
+		static LOGGED_POISON: AtomicBool = AtomicBool::new(false);
+		self.state.write().unwrap_or_else(|poisoned| {
+			if !LOGGED_POISON.swap(true, Ordering::Relaxed) {
+				log::error!(target: "input", "input state lock was poisoned by a panic, recovering");
+			}
+			poisoned.into_inner()
+		})
+	}
+
+	fn active_state(&self) -> ActiveState {
+		let state = self.read();
+		let active_layers = state.layer_order.iter();
+		let active_layers = active_layers.filter(|layer| state.active_layers.contains(*layer));
+		ActiveState {
+			active_layers: active_layers.cloned().collect(),
+			active_switches: state.active_switches.iter().cloned().collect(),
+		}
+	}
+
+	fn pressed_input(&self) -> PressedInput {
+		let state = self.read();
+		let key_name = |key: &rdev::Key| match state.key_aliases.get(key) {
+			Some(alias) => alias.to_string(),
+			None => format!("{key:?}"),
+		};
+		let mut pressed_keys = state.pressed_keys.iter().map(key_name).collect::<Vec<_>>();
+		pressed_keys.sort();
+		let mut pressed_hotkeys = state.pressed_hotkeys.iter().map(HotKey::to_string).collect::<Vec<_>>();
+		pressed_hotkeys.sort();
+		PressedInput {
+			pressed_keys,
+			pressed_hotkeys,
+		}
+	}
+
+	fn combo_stats(&self) -> BTreeMap<String, ComboStats> {
+		self.read().combo_stats.clone()
+	}
+
+	fn modifiers(&self) -> shared::ModifierState {
+		let state = self.read();
+		state.modifiers
+	}
+
 	fn init_app(&self, handle: tauri::AppHandle<tauri::Wry>) {
-		let mut state = self.0.write().expect("failed to open writing on input state");
+		let mut state = self.write();
 		state.app = Some(handle);
 	}
 
-	fn update_bindings(&self, config: &Config) {
+	pub(crate) fn update_bindings(&self, config: &Config) {
 		{
-			let mut state = self.0.write().expect("failed to open writing on input state");
+			let mut state = self.write();
 
 			let default_layer = config.layout().default_layer();
 			state.default_layer = default_layer.clone();
@@ -107,8 +506,24 @@ impl GlobalInputState {
 
 			state.layer_order = config.layout().layer_order().clone();
 			state.layer_switches.clear();
+			state.default_suppressors.clear();
+			state.combo_lockout = config.layout().combo_lockout();
+			state.stuck_input_timeout = config.layout().stuck_input_timeout();
+			state.switch_lockouts.clear();
+			state.combo_members.clear();
+			for combo in config.layout().combos() {
+				let members = combo.member_switches().cloned().collect();
+				state.combo_members.insert(combo.id.clone(), members);
+			}
 			for (layer_id, layer) in config.layout().layers() {
-				let switch_ids = layer.bindings().keys().map(Clone::clone).collect();
+				if layer.suppresses_default() {
+					state.default_suppressors.insert(layer_id.clone());
+				}
+				// layers which don't pass switches through block all of them, whether or not they bind them
+				let switch_ids = match layer.passthrough() {
+					true => layer.bindings().keys().cloned().collect(),
+					false => config.layout().switches().keys().cloned().collect(),
+				};
 				state.layer_switches.insert(layer_id.clone(), switch_ids);
 			}
 
@@ -116,42 +531,96 @@ impl GlobalInputState {
 			state.hotkey_bindings.clear();
 			state.pressed_keys.clear();
 			state.pressed_hotkeys.clear();
+			state.chords.clear();
+			state.pressed_chords.clear();
+			state.pending_holds.clear();
+			state.key_aliases.clear();
+			state.layer_step_hotkeys.clear();
 		}
 		self.insert_hotkeys(config);
+
+		let state = self.read();
+		for (hotkey, binding, other) in state.overlapping_hotkeys() {
+			log::warn!(
+				target: "config",
+				"Hotkey {hotkey} is bound to both switch={:?} slot={:?} and switch={:?} slot={:?} on the same layer, so both will be pressed",
+				binding.switch_id, binding.slot, other.switch_id, other.slot,
+			);
+		}
 	}
 
 	fn insert_hotkeys(&self, config: &Config) {
+		let keymap = config.keymap();
 		for (layer_id, layer) in config.layout().layers() {
 			let layer_id = Arc::new(layer_id.clone());
 			for (switch_id, bindings) in layer.bindings() {
 				let switch_id = Arc::new(switch_id.clone());
 				for (slot, binding) in &bindings.slots {
+					if let Some(device) = &binding.device {
+						log::warn!(
+							target: "config",
+							"Switch {switch_id:?} on layer {layer_id:?} is limited to device {device:?}, but keyboards can't be told apart on this platform, so it is triggered by every keyboard"
+						);
+					}
 					let target_layer = binding.layer.as_ref().map(Clone::clone).map(Arc::new);
-					self.insert_binding(InputBinding {
-						layer_id: [layer_id.clone()].into(),
-						switch_id: switch_id.clone(),
-						slot: Some(*slot),
-						target_layer,
-						key: binding.input.clone(),
-					});
+					self.insert_binding(
+						InputBinding {
+							layer_id: [layer_id.clone()].into(),
+							switch_id: switch_id.clone(),
+							slot: Some(*slot),
+							target_layer,
+							key: binding.detected_input().clone(),
+							on_press: binding.on_press.clone().map(Arc::new),
+							hold: None,
+							priority: binding.priority(),
+							device: binding.device.clone().map(Arc::new),
+						},
+						&keymap,
+					);
 				}
 			}
 		}
 		for combo in config.layout().combos() {
 			let target_layer = combo.input_layer.as_ref().map(Clone::clone).map(Arc::new);
-			self.insert_binding(InputBinding {
-				layer_id: HashSet::default(),
-				switch_id: Arc::new(combo.id.clone()),
-				slot: None,
-				target_layer,
-				key: combo.input.clone(),
-			});
+			let priority = shared::default_binding_priority(target_layer.is_some());
+			self.insert_binding(
+				InputBinding {
+					layer_id: combo.layers.iter().cloned().map(Arc::new).collect(),
+					switch_id: Arc::new(combo.id.clone()),
+					slot: None,
+					target_layer,
+					key: combo.input.clone(),
+					on_press: None,
+					hold: combo.hold_ms.map(std::time::Duration::from_millis),
+					priority,
+					device: None,
+				},
+				&keymap,
+			);
+		}
+		let mut state = self.write();
+		for (step, keys) in config.layer_step_hotkeys() {
+			for hotkey in alias_hotkeys(keys, &keymap) {
+				for code in hotkey.relevant_keys() {
+					state.key_to_relevant_hotkeys.insert(code, hotkey);
+				}
+				state.layer_step_hotkeys.insert(hotkey, step);
+			}
 		}
 	}
 
-	fn insert_binding(&self, input_binding: InputBinding) {
-		let mut state = self.0.write().expect("failed to open writing on input state");
-		for hotkey in alias_hotkeys(&input_binding.key) {
+	fn insert_binding(&self, input_binding: InputBinding, keymap: &Keymap) {
+		let mut state = self.write();
+		for alias in input_binding.key.iter() {
+			if let Some(code) = keymap.code(*alias) {
+				state.key_aliases.entry(code).or_insert(*alias);
+			}
+		}
+		if let Some(keys) = chord_keys(&input_binding.key, keymap) {
+			state.chords.push((keys, input_binding));
+			return;
+		}
+		for hotkey in alias_hotkeys(&input_binding.key, keymap) {
 			for code in hotkey.relevant_keys() {
 				state.key_to_relevant_hotkeys.insert(code, hotkey);
 			}
@@ -159,96 +628,326 @@ impl GlobalInputState {
 		}
 	}
 
-	fn handle(&self, event: &rdev::Event) {
-		let mut state = self.0.write().expect("failed to open writing on input state");
-		let key = match event.event_type {
-			rdev::EventType::KeyPress(key) => {
-				state.pressed_keys.insert(key);
-				key
-			}
-			rdev::EventType::KeyRelease(key) => {
-				state.pressed_keys.remove(&key);
-				key
-			}
-			_ => return,
+	/// Releases all held keys if no key events have been received for the layout's `stuck_input_timeout_ms`.
+	/// Held keys repeat their press events, so a long silence means a release was missed (e.g. when alt-tabbing).
+	fn clear_if_idle(&self) {
+		// frozen input is expected to be idle, and should keep showing whatever was held
+		if self.is_frozen() {
+			return;
+		}
+		let mut state = self.write();
+		let (Some(timeout), Some(idle_duration)) = (state.stuck_input_timeout, state.idle_duration()) else {
+			return;
+		};
+		if idle_duration < timeout || state.pressed_keys.is_empty() {
+			return;
+		}
+		log::warn!(target: "input", "No input for {timeout:?} while keys are held, clearing stuck keys {:?}", state.pressed_keys);
+		state.release_all();
+	}
+
+	/// Releases all held keys when the overlay regains focus, since releases can be missed while it is unfocused.
+	fn clear_on_focus(&self) {
+		if self.is_frozen() {
+			return;
+		}
+		let mut state = self.write();
+		if state.pressed_keys.is_empty() {
+			return;
+		}
+		log::debug!(target: "input", "Overlay was focused, clearing held keys {:?}", state.pressed_keys);
+		state.release_all();
+	}
+
+	fn is_frozen(&self) -> bool {
+		self.frozen.load(Ordering::Relaxed)
+	}
+
+	/// Pauses or resumes input processing.
+	/// Keys released while frozen are never seen, so everything is released when input resumes.
+	fn set_frozen(&self, frozen: bool) {
+		let was_frozen = self.frozen.swap(frozen, Ordering::Relaxed);
+		if was_frozen && !frozen {
+			self.write().release_all();
+		}
+	}
+
+	/// Presses a switch as if its binding was triggered by real input, releasing it after `duration`.
+	fn simulate_press(&self, switch_id: String, slot: Option<shared::SwitchSlot>, duration: std::time::Duration) {
+		let pressed = shared::InputUpdate::SwitchPressed(switch_id.clone(), slot);
+		self.write().commit_updates(vec![pressed], Vec::new());
+
+		let input = self.clone();
+		std::thread::spawn(move || {
+			std::thread::sleep(duration);
+			let released = shared::InputUpdate::SwitchReleased(switch_id);
+			input.write().commit_updates(vec![released], Vec::new());
+		});
+	}
+
+	/// Activates or deactivates a layer directly, instead of through the bindings which activate it.
+	/// Bindings which activate the layer still deactivate it when they are released.
+	fn set_layer_active(&self, layer: String, active: bool) {
+		let update = match active {
+			true => shared::InputUpdate::LayerActivate(layer),
+			false => shared::InputUpdate::LayerDeactivate(layer),
 		};
+		self.write().commit_updates(vec![update], Vec::new());
+	}
 
-		let Some(hotkeys) = state.key_to_relevant_hotkeys.get_vec(&key).cloned() else {
+	pub(crate) fn handle(&self, event: &rdev::Event) {
+		if self.is_frozen() {
 			return;
+		}
+		let (key, is_press) = match event.event_type {
+			rdev::EventType::KeyPress(key) => (key, true),
+			rdev::EventType::KeyRelease(key) => (key, false),
+			_ => return,
+		};
+
+		// Held keys repeat their press events, which are the bulk of events while typing.
+		// Those don't change anything but when input was last received, so they only need the read lock.
+		{
+			let state = self.read();
+			state.mark_event();
+			if state.pressed_keys.contains(&key) == is_press {
+				return;
+			}
+		}
+
+		let mut guard = self.write();
+		// reborrow through the guard once, so disjoint fields can be borrowed at the same time
+		let state = &mut *guard;
+		let device = event_device(event);
+		let previous_modifiers = state.modifiers;
+		let changed = match is_press {
+			true => state.pressed_keys.insert(key),
+			false => state.pressed_keys.remove(&key),
 		};
+		// the key may have been cleared as stuck between releasing the read lock and acquiring the write lock
+		if !changed {
+			return;
+		}
+		if is_press {
+			state.toggle_lock(key);
+		}
+		state.update_modifiers(previous_modifiers);
 
-		let mut changed_hotkeys = HashSet::with_capacity(10);
+		let hotkeys = state.key_to_relevant_hotkeys.get_vec(&key).map(Vec::as_slice);
+		let hotkeys = hotkeys.unwrap_or_default();
+		let mut changed_bindings = Vec::with_capacity(hotkeys.len());
+		let mut updates = Vec::with_capacity(hotkeys.len() * 2);
 		for hotkey in hotkeys {
-			if hotkey.is_pressed(&state.pressed_keys) {
-				if state.pressed_hotkeys.insert(hotkey) {
-					changed_hotkeys.insert(hotkey);
+			let pressed = hotkey.is_pressed(&state.pressed_keys);
+			let changed = match pressed {
+				true => state.pressed_hotkeys.insert(*hotkey),
+				false => state.pressed_hotkeys.remove(hotkey),
+			};
+			if !changed {
+				continue;
+			}
+			// Stepping swaps the previously stepped layer for the next one, independent of any layer bindings
+			if let (true, Some(step)) = (pressed, state.layer_step_hotkeys.get(hotkey)) {
+				let stepped = step.step(&state.layer_order, &state.default_layer, state.stepped_layer.as_ref());
+				let stepped = stepped.cloned();
+				log::debug!(target: "input", "stepped {step:?} to layer {stepped:?}");
+				if let Some(layer) = std::mem::replace(&mut state.stepped_layer, stepped.clone()) {
+					updates.push(shared::InputUpdate::LayerDeactivate(layer));
 				}
-			} else {
-				if state.pressed_hotkeys.remove(&hotkey) {
-					changed_hotkeys.insert(hotkey);
+				if let Some(layer) = stepped {
+					updates.push(shared::InputUpdate::LayerActivate(layer));
 				}
 			}
+			let bindings = state.hotkey_bindings.get_vec(hotkey).map(Vec::as_slice);
+			let bindings = bindings.unwrap_or_default().iter();
+			for binding in bindings.filter(|binding| binding.matches_device(device)) {
+				changed_bindings.push((pressed, binding, InputTrigger::HotKey(hotkey)));
+			}
 		}
-
-		let mut updates = Vec::new();
-		for hotkey in changed_hotkeys {
-			let pressed = state.pressed_hotkeys.contains(&hotkey);
-			if let Some(bindings) = state.hotkey_bindings.get_vec(&hotkey).cloned() {
-				for binding in bindings {
-					if pressed && state.can_trigger(&binding) {
-						if let Some(new_layer) = &binding.target_layer {
-							updates.push(shared::InputUpdate::LayerActivate((**new_layer).clone()));
-						}
-						updates.push(shared::InputUpdate::SwitchPressed(
-							(*binding.switch_id).clone(),
-							binding.slot,
-						));
-					} else if !pressed {
-						if let Some(layer) = &binding.target_layer {
-							updates.push(shared::InputUpdate::LayerDeactivate((**layer).clone()));
-						}
-						updates.push(shared::InputUpdate::SwitchReleased((*binding.switch_id).clone()));
-					}
+		// Chords are pressed once all of their keys are held, and released as soon as any of them is released
+		for (idx, (keys, binding)) in state.chords.iter().enumerate() {
+			if !keys.contains(&key) {
+				continue;
+			}
+			// pressing the first key of a chord combo is an attempt at it, whether or not the chord is completed
+			let held_keys = keys.iter().filter(|key| state.pressed_keys.contains(key));
+			if is_press && held_keys.count() == 1 {
+				if let Some(stats) = ComboStats::of(&mut state.combo_stats, binding) {
+					stats.attempts += 1;
 				}
 			}
+			let pressed = keys.is_subset(&state.pressed_keys);
+			let changed = match pressed {
+				true => state.pressed_chords.insert(idx),
+				false => state.pressed_chords.remove(&idx),
+			};
+			if changed {
+				changed_bindings.push((pressed, binding, InputTrigger::Chord(keys)));
+			}
 		}
+		sort_by_priority(&mut changed_bindings);
 
-		for update in updates {
-			match &update {
-				shared::InputUpdate::LayerActivate(layer) => {
-					state.active_layers.insert(layer.clone());
+		updates.reserve(changed_bindings.len() * 2);
+		let mut effects = Vec::new();
+		let mut holds = Vec::new();
+		for (pressed, binding, trigger) in changed_bindings {
+			let (switch_id, slot, target_layer) = (&binding.switch_id, binding.slot, &binding.target_layer);
+			let layer = pressed.then(|| state.triggering_layer(binding)).flatten();
+			match (pressed, layer) {
+				(true, Some(layer)) => log::trace!(
+					target: "input::trace",
+					"press switch={switch_id:?} slot={slot:?} layer={layer:?} target_layer={target_layer:?} via {trigger}"
+				),
+				(true, None) => log::trace!(
+					target: "input::trace",
+					"ignore switch={switch_id:?} slot={slot:?} via {trigger}, blocked or not on an active layer"
+				),
+				(false, _) => log::trace!(
+					target: "input::trace",
+					"release switch={switch_id:?} slot={slot:?} target_layer={target_layer:?} via {trigger}"
+				),
+			}
+			let triggered = layer.is_some();
+			if slot.is_none() && (triggered || !pressed) {
+				state.lock_out_combo_members(switch_id);
+			}
+			if let (true, InputTrigger::HotKey(_)) = (triggered, &trigger) {
+				if let Some(stats) = ComboStats::of(&mut state.combo_stats, binding) {
+					stats.attempts += 1;
 				}
-				shared::InputUpdate::LayerDeactivate(layer) => {
-					state.active_layers.remove(layer);
+			}
+			if triggered && binding.hold.is_some() {
+				// the binding only triggers once it has been held long enough, see `complete_hold`
+				state.hold_generation += 1;
+				let generation = state.hold_generation;
+				state.pending_holds.insert(binding.switch_id.clone(), generation);
+				updates.push(shared::InputUpdate::HoldStarted((*binding.switch_id).clone()));
+				holds.push((binding.clone(), generation));
+			} else if triggered {
+				if let Some(stats) = ComboStats::of(&mut state.combo_stats, binding) {
+					stats.successes += 1;
 				}
-				shared::InputUpdate::SwitchPressed(switch_id, _slot) => {
-					state.active_switches.insert(switch_id.clone());
+				push_press_updates(binding, &mut updates, &mut effects);
+			} else if !pressed && state.pending_holds.remove(&binding.switch_id).is_some() {
+				if let Some(stats) = ComboStats::of(&mut state.combo_stats, binding) {
+					stats.timed_out += 1;
 				}
-				shared::InputUpdate::SwitchReleased(switch_id) => {
-					state.active_switches.remove(switch_id);
+				updates.push(shared::InputUpdate::HoldCancelled((*binding.switch_id).clone()));
+			} else if !pressed {
+				if let Some(layer) = &binding.target_layer {
+					updates.push(shared::InputUpdate::LayerDeactivate((**layer).clone()));
 				}
+				updates.push(shared::InputUpdate::SwitchReleased((*binding.switch_id).clone()));
 			}
+		}
+		state.commit_updates(updates, effects);
+		drop(guard);
 
-			if let Some(app) = &state.app {
-				let _ = app.emit_all("input", update);
-			}
+		for (binding, generation) in holds {
+			let Some(hold) = binding.hold else { continue };
+			let input = self.clone();
+			std::thread::spawn(move || {
+				std::thread::sleep(hold);
+				input.complete_hold(&binding, generation);
+			});
 		}
 	}
+
+	/// Triggers a binding which must be held, if it has been held since the press which started this hold.
+	fn complete_hold(&self, binding: &InputBinding, generation: u64) {
+		// holds which complete while frozen are cancelled when input resumes
+		if self.is_frozen() {
+			return;
+		}
+		let mut state = self.write();
+		if state.pending_holds.get(&binding.switch_id) != Some(&generation) {
+			return;
+		}
+		state.pending_holds.remove(&binding.switch_id);
+		if let Some(stats) = ComboStats::of(&mut state.combo_stats, binding) {
+			stats.successes += 1;
+		}
+		let mut updates = Vec::new();
+		let mut effects = Vec::new();
+		push_press_updates(binding, &mut updates, &mut effects);
+		state.commit_updates(updates, effects);
+	}
+}
+
+/// Orders the bindings changed by a single key event so the highest priority bindings are emitted first.
+/// Layer bindings default to a higher priority, so when a key is bound to both a layer and a switch,
+/// the layer is activated before the switch is pressed. Bindings with the same priority keep their order.
+fn sort_by_priority<T>(changed_bindings: &mut [(bool, &InputBinding, T)]) {
+	changed_bindings.sort_by_key(|(_, binding, _)| std::cmp::Reverse(binding.priority));
+}
+
+/// Adds the updates (and effects) for pressing a binding which has triggered.
+fn push_press_updates(binding: &InputBinding, updates: &mut Vec<shared::InputUpdate>, effects: &mut Vec<String>) {
+	if let Some(effect) = &binding.on_press {
+		effects.push((**effect).clone());
+	}
+	if let Some(new_layer) = &binding.target_layer {
+		updates.push(shared::InputUpdate::LayerActivate((**new_layer).clone()));
+	}
+	updates.push(shared::InputUpdate::SwitchPressed(
+		(*binding.switch_id).clone(),
+		binding.slot,
+	));
+}
+
+/// Queues an event from the os input hook to be processed.
+/// Presses (including repeats) are dropped if the queue is full, but a dropped release would leave its key held,
+/// so releases wait for room in the queue instead.
+fn queue_event(send_event: &std::sync::mpsc::SyncSender<rdev::Event>, event: rdev::Event) {
+	let is_release = matches!(
+		event.event_type,
+		rdev::EventType::KeyRelease(_) | rdev::EventType::ButtonRelease(_)
+	);
+	if is_release {
+		let _ = send_event.send(event);
+		return;
+	}
+	if let Err(std::sync::mpsc::TrySendError::Full(event)) = send_event.try_send(event) {
+		log::warn!(target: "rdev", "Input queue is full, dropping {:?}", event.event_type);
+	}
 }
 
 fn main() -> anyhow::Result<()> {
+	let args = LaunchArgs::from_env();
+	// Piped configs are read before the app starts, parsing errors are reported once the app has launched
+	let stdin_config = args.config_from_stdin.then(read_config_stdin);
+
 	let global_input = GlobalInputState::default();
+	// The os waits on the grab callback before delivering each event, so the callback only queues the event.
+	// Processing and emitting input happens on its own thread, so the overlay can never lag the user's typing.
+	let (send_event, recv_event) = std::sync::mpsc::sync_channel::<rdev::Event>(INPUT_QUEUE_CAPACITY);
+	std::thread::spawn(move || {
+		if let Err(err) = rdev::grab(move |event| {
+			queue_event(&send_event, event.clone());
+			Some(event)
+		}) {
+			log::error!(target: "rdev", "{err:?}");
+		}
+	});
 	std::thread::spawn({
 		let input = global_input.clone();
 		move || {
-			if let Err(err) = rdev::grab(move |event| {
+			for event in recv_event {
 				input.handle(&event);
-				Some(event)
-			}) {
-				log::error!(target: "rdev", "{err:?}");
 			}
 		}
 	});
+	std::thread::spawn({
+		let input = global_input.clone();
+		move || loop {
+			std::thread::sleep(std::time::Duration::from_secs(1));
+			input.clear_if_idle();
+		}
+	});
+	if let Some(port) = args.state_port {
+		let input = global_input.clone();
+		spawn_state_server(port, move || input.active_state());
+	}
 
 	tauri::Builder::default()
 		.plugin(
@@ -268,8 +967,27 @@ fn main() -> anyhow::Result<()> {
 		.plugin(tauri_plugin_positioner::init())
 		.plugin(tauri_plugin_clipboard::init())
 		.manage(ConfigMutex::default())
+		.manage(DisplayFlagsMutex::default())
+		.manage(ConfigErrorMutex::default())
 		.manage(global_input)
-		.setup(|app| {
+		.manage(args.clone())
+		.invoke_handler(tauri::generate_handler![
+			export_layout_svg,
+			validate_config,
+			set_scale,
+			highlight_switch,
+			set_frozen,
+			test_switch,
+			move_to_next_monitor,
+			get_input_state,
+			set_layer_active,
+			get_combo_stats,
+			export_combo_stats_csv,
+			dump_resolved_config,
+			list_hotkeys,
+			reset_profile
+		])
+		.setup(move |app| {
 			// Listen for logging from the frontend
 			app.listen_global("log", |event| {
 				let Some(payload_str) = event.payload() else { return };
@@ -278,6 +996,11 @@ fn main() -> anyhow::Result<()> {
 				};
 				log::log!(target: record.target.as_str(), record.level, "{}", record.args);
 			});
+			// The frontend can request a reload (via a shortcut in debug builds)
+			app.listen_global("reload_config", {
+				let app = app.handle();
+				move |_| reload_config(&app)
+			});
 			// Wait for the frontend to become ready
 			app.listen_global("ready", {
 				let app = app.handle();
@@ -287,8 +1010,13 @@ fn main() -> anyhow::Result<()> {
 
 					let icon_scale = config.active_profile().map(|profile| profile.scale).unwrap_or(1.0);
 					let _ = app.emit_all("scale", icon_scale);
+					let profile_layers = config.active_profile().map(|profile| profile.layers.clone());
+					let _ = app.emit_all("profile_layers", profile_layers.unwrap_or_default());
 
 					let _ = app.emit_all("layout", config.layout().clone());
+					let _ = app.emit_all("flags", app.state::<DisplayFlagsMutex>().get());
+					let _ = app.emit_all("config_error", app.state::<ConfigErrorMutex>().get());
+					let _ = app.emit_all("modifiers", app.state::<GlobalInputState>().modifiers());
 					let _ = app.emit_all(
 						"input",
 						shared::InputUpdate::LayerActivate(config.layout().default_layer().clone()),
@@ -296,13 +1024,6 @@ fn main() -> anyhow::Result<()> {
 				}
 			});
 
-			let window = app.get_window("main").ok_or(tauri::Error::InvalidWindowHandle)?;
-
-			// If not in debug mode, then ignore cursor events on the window
-			if !cfg!(debug_assertions) {
-				window.set_ignore_cursor_events(true)?;
-			}
-
 			// Associate the app to global_input so that when input changes, it can be propagated to app events.
 			{
 				let global_input = app.state::<GlobalInputState>();
@@ -317,22 +1038,100 @@ fn main() -> anyhow::Result<()> {
 					let Ok(config) = serde_json::from_str::<Config>(payload) else {
 						return;
 					};
-					let _ = app.emit_all("layout", config.layout().clone());
 					let global_input = app.state::<GlobalInputState>();
 					global_input.update_bindings(&config);
 				}
 			});
 
-			// Load the config as it exists on startup
-			if let Some(config) = load_config(&app.config())? {
-				if let Some(profile) = config.active_profile() {
-					apply_initial_window_location(&app.handle(), profile)?;
+			// Load the config as it exists on startup (or as it was piped in)
+			let watch_config_file = stdin_config.is_none();
+			let initial_config = match (args.safe_mode, stdin_config) {
+				(true, _) => {
+					log::warn!(target: "config", "Launching in safe mode, the saved config is ignored");
+					Ok(Some(Config::default()))
 				}
+				(false, Some(config)) => config.map(Some),
+				(false, None) => load_config(&app.config()),
+			};
+			// A broken config should never prevent the app from launching, so fall back to the default config
+			let initial_config = initial_config.unwrap_or_else(|err| {
+				log::error!(target: "config", "Failed to load config, launching with the default config: {err:?}");
+				set_config_error(&app.handle(), Some(format!("{err:#}")));
+				Some(Config::default())
+			});
+			// Without a saved config (e.g. on first run), the built-in default config is shown until it is saved
+			let using_default_config = initial_config.is_none();
+			let initial_config = initial_config.or_else(|| match default_config() {
+				Ok(config) => Some(config),
+				Err(err) => {
+					log::error!(target: "config", "Failed to load the built-in default config: {err:?}");
+					None
+				}
+			});
+			if let Some(config) = initial_config {
+				// A config without any profiles still needs the window to be sized and visible
+				let profile = config.active_profile().cloned().unwrap_or_default();
+				apply_initial_window_location(&app.handle(), &profile)?;
 				set_config(&app.handle(), config)?;
 			}
 
+			// The config chooses which window displays the overlay, so it can only be found once the config is loaded
+			let window = overlay_window(&app.handle())?;
+
+			if using_default_config && window.is_some() {
+				offer_to_save_config(&app.handle());
+			}
+
+			// If not in debug mode, then ignore cursor events on the window
+			if let Some(window) = window.as_ref().filter(|_| !cfg!(debug_assertions)) {
+				window.set_ignore_cursor_events(true)?;
+			}
+
+			// Switch to the profile targeting a monitor whenever the overlay is moved onto that monitor,
+			// keep the layout's proportions when the overlay is resized, and clear stuck keys when it is focused
+			if let Some(window) = window.as_ref() {
+				let app = app.handle();
+				window.on_window_event(move |event| match event {
+					tauri::WindowEvent::Focused(true) => {
+						app.state::<GlobalInputState>().clear_on_focus();
+					}
+					tauri::WindowEvent::Moved(_) => {
+						let app = app.clone();
+						spawn("config", async move { select_monitor_profile(&app) });
+					}
+					tauri::WindowEvent::Resized(size) => {
+						let app = app.clone();
+						let size = (size.width, size.height);
+						spawn("window", async move { lock_window_aspect(&app, size) });
+					}
+					_ => {}
+				});
+			}
+
+			// Reload the config when it is edited outside of the app
+			if let Some(config_path) = config_file_path(&app.config()).filter(|_| watch_config_file) {
+				let app = app.handle();
+				spawn_config_watcher(config_path, move |contents| match parse_config_kdl(&contents) {
+					Ok(config) => {
+						log_config_changes(&app, &config);
+						if let Err(err) = set_config(&app, config) {
+							log::error!(target: "config", "{err:?}");
+						}
+						set_config_error(&app, None);
+					}
+					Err(err) => {
+						log::error!(target: "config", "Failed to reload config: {err:?}");
+						set_config_error(&app, Some(format!("{err:#}")));
+					}
+				});
+			}
+
 			SystemTray::new()
-				.with_menu(build_system_tray_menu(&app.state::<ConfigMutex>().get()))
+				.with_menu(build_system_tray_menu(
+					&app.state::<ConfigMutex>().get(),
+					&app.state::<DisplayFlagsMutex>().get(),
+					app.state::<GlobalInputState>().is_frozen(),
+				))
 				.on_event({
 					let app = app.handle();
 					move |event| {
@@ -343,7 +1142,7 @@ fn main() -> anyhow::Result<()> {
 									app.exit(0);
 								}
 								id if id == MENU_TOGGLE_ID => {
-									let Some(window) = app.get_window("main") else { return };
+									let Ok(Some(window)) = overlay_window(&app) else { return };
 									window.trigger(EVENT_TOGGLE_WINDOW_VISIBILITY, None);
 								}
 								id if id == TRAY_CONFIG_OPEN_DIR.0 => {
@@ -357,17 +1156,13 @@ fn main() -> anyhow::Result<()> {
 									};
 									log::error!("failed to open config directory {config_path_str:?}: {err:?}");
 								}
-								id if id == TRAY_CONFIG_RELOAD.0 => match load_config(&app.config()) {
-									Ok(Some(config)) => {
-										if let Err(err) = set_config(&app, config) {
-											log::error!("{err:?}");
-										}
-									}
-									Ok(None) => {}
-									Err(err) => {
-										log::error!("{err:?}");
+								id if id == TRAY_CONFIG_RELOAD.0 => reload_config(&app),
+								id if id == TRAY_RESET_PROFILE.0 => {
+									let profile_name = app.state::<ConfigMutex>().get().active_profile_id().clone();
+									if let Err(err) = reset_and_apply_profile(&app, &profile_name) {
+										log::error!(target: "config", "{err:?}");
 									}
-								},
+								}
 								id if id.starts_with("profile:") => {
 									let Some(profile_name) = id.strip_prefix("profile:") else {
 										return;
@@ -380,7 +1175,7 @@ fn main() -> anyhow::Result<()> {
 									let Ok(config_payload) = serde_json::to_string(&config) else {
 										return;
 									};
-									let _ = save_config(&app.config(), &config);
+									let _ = save_app_config(&app, &config);
 									config_state.set(config);
 									app.trigger_global("config:profile", Some(config_payload));
 								}
@@ -399,8 +1194,14 @@ fn main() -> anyhow::Result<()> {
 											let app = app.clone();
 											spawn("config", async move {
 												log::info!("Uploading config from url {url}");
-												let response = reqwest::get(url).await?;
-												let contents = response.text().await?;
+												let contents = match reqwest::get(url).await {
+													Ok(response) => response.text().await,
+													Err(err) => Err(err),
+												};
+												let contents = contents.map_err(|err| {
+													notify(&app, "Config import failed", &err.to_string());
+													err
+												})?;
 												upload_config(&app, &contents)?;
 												Ok(()) as anyhow::Result<()>
 											});
@@ -419,6 +1220,69 @@ fn main() -> anyhow::Result<()> {
 									let clipboard = app.state::<tauri_plugin_clipboard::ClipboardManager>();
 									let _ = clipboard.write_text(serialize_config_kdl(&config));
 								}
+								id if id == TRAY_FLAG_DESIGN_GRID.0 => {
+									let flags = app.state::<DisplayFlagsMutex>().update(|flags| {
+										flags.design_grid = !flags.design_grid;
+									});
+									let menu_item = app.tray_handle().get_item(TRAY_FLAG_DESIGN_GRID.0);
+									let _ = menu_item.set_selected(flags.design_grid);
+									let _ = app.emit_all("flags", flags);
+								}
+								id if id == TRAY_FLAG_ALL_LAYERS.0 => {
+									let flags = app.state::<DisplayFlagsMutex>().update(|flags| {
+										flags.all_layers = !flags.all_layers;
+									});
+									let menu_item = app.tray_handle().get_item(TRAY_FLAG_ALL_LAYERS.0);
+									let _ = menu_item.set_selected(flags.all_layers);
+									let _ = app.emit_all("flags", flags);
+								}
+								id if id == TRAY_NEXT_MONITOR.0 => {
+									if let Err(err) = move_to_next_monitor(app.clone()) {
+										log::error!(target: "window", "{err}");
+									}
+								}
+								id if id == TRAY_COPY_GEOMETRY.0 => match window_geometry_kdl(&app) {
+									Ok(Some(kdl)) => {
+										let clipboard = app.state::<tauri_plugin_clipboard::ClipboardManager>();
+										let _ = clipboard.write_text(kdl);
+									}
+									Ok(None) => {}
+									Err(err) => log::error!(target: "window", "{err:?}"),
+								},
+								id if id == TRAY_SCREENSHOT.0 => {
+									let app = app.clone();
+									// give the tray menu time to close, so it isn't captured over the overlay
+									std::thread::spawn(move || {
+										std::thread::sleep(SCREENSHOT_DELAY);
+										match screenshot_overlay(&app) {
+											Ok(Some(path)) => {
+												notify(&app, "Screenshot saved", &path.display().to_string())
+											}
+											Ok(None) => {}
+											Err(err) => {
+												log::error!(target: "window", "Failed to screenshot overlay: {err:?}")
+											}
+										}
+									});
+								}
+								id if id == TRAY_MIRROR_WINDOW.0 => {
+									let app = app.clone();
+									spawn("window", async move { open_mirror_window(&app) });
+								}
+								id if id == TRAY_FREEZE.0 => {
+									let frozen = !app.state::<GlobalInputState>().is_frozen();
+									set_frozen(app.clone(), frozen);
+								}
+								id if id == TRAY_LAYOUT_EXPORT_SVG.0 => {
+									let svg = export_layout_svg(app.state::<ConfigMutex>());
+									let clipboard = app.state::<tauri_plugin_clipboard::ClipboardManager>();
+									let _ = clipboard.write_text(svg);
+								}
+								id if id == TRAY_COMBO_STATS.0 => {
+									let csv = export_combo_stats_csv(app.state::<GlobalInputState>());
+									let clipboard = app.state::<tauri_plugin_clipboard::ClipboardManager>();
+									let _ = clipboard.write_text(csv);
+								}
 								_ => {}
 							},
 							_ => {}
@@ -428,21 +1292,23 @@ fn main() -> anyhow::Result<()> {
 				.build(app)?;
 
 			// Handle toggling the window visibility
-			window.listen(EVENT_TOGGLE_WINDOW_VISIBILITY, {
-				let app = app.handle();
-				move |_event| {
-					let Some(window) = app.get_window("main") else { return };
-					let Ok(is_visible) = window.is_visible() else { return };
-					let menu_item = app.tray_handle().get_item(MENU_TOGGLE_ID);
-					if is_visible {
-						let Ok(_) = window.hide() else { return };
-						let _ = menu_item.set_title(MENU_TOGGLE_SHOW);
-					} else {
-						let Ok(_) = window.show() else { return };
-						let _ = menu_item.set_title(MENU_TOGGLE_HIDE);
+			if let Some(window) = &window {
+				window.listen(EVENT_TOGGLE_WINDOW_VISIBILITY, {
+					let app = app.handle();
+					move |_event| {
+						let Ok(Some(window)) = overlay_window(&app) else { return };
+						let Ok(is_visible) = window.is_visible() else { return };
+						let menu_item = app.tray_handle().get_item(MENU_TOGGLE_ID);
+						if is_visible {
+							let Ok(_) = window.hide() else { return };
+							let _ = menu_item.set_title(MENU_TOGGLE_SHOW);
+						} else {
+							let Ok(_) = window.show() else { return };
+							let _ = menu_item.set_title(MENU_TOGGLE_HIDE);
+						}
 					}
-				}
-			});
+				});
+			}
 
 			// When the config loads, rebuild the system tray menu (to account for display profiles loading)
 			app.listen_global("config", {
@@ -452,7 +1318,11 @@ fn main() -> anyhow::Result<()> {
 					let Ok(config) = serde_json::from_str(payload) else {
 						return;
 					};
-					let _ = app_handle.tray_handle().set_menu(build_system_tray_menu(&config));
+					let flags = app_handle.state::<DisplayFlagsMutex>().get();
+					let frozen = app_handle.state::<GlobalInputState>().is_frozen();
+					let _ = app_handle
+						.tray_handle()
+						.set_menu(build_system_tray_menu(&config, &flags, frozen));
 				}
 			});
 
@@ -464,9 +1334,10 @@ fn main() -> anyhow::Result<()> {
 					let Ok(config) = serde_json::from_str::<Config>(payload) else {
 						return;
 					};
-					let Some(profile) = config.active_profile() else { return };
-					let _ = apply_initial_window_location(&app, profile);
+					let profile = config.active_profile().cloned().unwrap_or_default();
+					let _ = apply_initial_window_location(&app, &profile);
 					let _ = app.emit_all("scale", profile.scale);
+					let _ = app.emit_all("profile_layers", profile.layers);
 				}
 			});
 
@@ -476,76 +1347,541 @@ fn main() -> anyhow::Result<()> {
 	Ok(())
 }
 
+/// Renders the current layout, at the size of the active display profile, as an svg image.
+#[tauri::command]
+fn export_layout_svg(config: tauri::State<'_, ConfigMutex>) -> String {
+	let config = config.get();
+	let profile = config.active_profile().cloned().unwrap_or_default();
+	render_layout_svg(config.layout(), profile.size)
+}
+
+/// Changes the scale of the active display profile at runtime, resizing the overlay and saving the config.
+#[tauri::command]
+fn set_scale(app: tauri::AppHandle<tauri::Wry>, scale: f64) -> Result<(), String> {
+	if !scale.is_finite() || scale <= 0.0 {
+		return Err(format!("Invalid scale {scale}, must be greater than zero"));
+	}
+	let config_state = app.state::<ConfigMutex>();
+	let mut config = config_state.get();
+	let Some(profile) = config.active_profile_mut() else {
+		return Err("There is no active profile to scale".into());
+	};
+	profile.scale = scale;
+	let profile = profile.clone();
+
+	save_app_config(&app, &config).map_err(|err| format!("{err:?}"))?;
+	config_state.set(config);
+	let _ = app.emit_all("scale", scale);
+
+	let Some(window) = overlay_window(&app).map_err(|err| err.to_string())? else {
+		return Ok(());
+	};
+	apply_window_size(&window, &profile).map_err(|err| format!("{err:?}"))?;
+	Ok(())
+}
+
+/// Resets the size, location, and scale of a profile to the defaults, such as when it was moved off-screen.
+#[tauri::command]
+fn reset_profile(app: tauri::AppHandle<tauri::Wry>, name: String) -> Result<(), String> {
+	reset_and_apply_profile(&app, &name).map_err(|err| format!("{err:?}"))
+}
+
+/// Resets the named profile and saves the config, re-applying the profile if it is the active profile.
+fn reset_and_apply_profile(app: &tauri::AppHandle<tauri::Wry>, name: &str) -> anyhow::Result<()> {
+	let config_state = app.state::<ConfigMutex>();
+	let mut config = config_state.get();
+	config.reset_profile(name)?;
+	save_app_config(app, &config)?;
+	let is_active = config.active_profile_id() == name;
+	let config_payload = serde_json::to_string(&config)?;
+	config_state.set(config);
+	if is_active {
+		app.trigger_global("config:profile", Some(config_payload));
+	}
+	log::info!(target: "config", "Reset profile {name:?} to the default geometry");
+	Ok(())
+}
+
+/// Moves the overlay to the next monitor (wrapping around to the first), saving it as the active profile's monitor.
+#[tauri::command]
+fn move_to_next_monitor(app: tauri::AppHandle<tauri::Wry>) -> Result<(), String> {
+	let Some(window) = overlay_window(&app).map_err(|err| err.to_string())? else {
+		return Ok(());
+	};
+	let monitor_count = window.available_monitors().map_err(|err| format!("{err:?}"))?.len();
+	let config_state = app.state::<ConfigMutex>();
+	let mut config = config_state.get();
+	let Some(profile) = config.active_profile_mut() else {
+		return Err("There is no active profile to move".into());
+	};
+	profile.location.monitor = (profile.location.monitor + 1) % monitor_count.max(1);
+	let location = profile.location;
+
+	save_app_config(&app, &config).map_err(|err| format!("{err:?}"))?;
+	config_state.set(config);
+	move_window_to_position(&window, location).map_err(|err| format!("{err:?}"))?;
+	Ok(())
+}
+
+/// Opens an opaque, decorated window which mirrors the overlay, to use as a window capture source
+/// when the transparent overlay can't be captured directly. It receives the same events as the overlay,
+/// so it shows the same layout and input. Focuses the mirror instead if it is already open.
+fn open_mirror_window(app: &tauri::AppHandle<tauri::Wry>) -> anyhow::Result<()> {
+	if let Some(mirror) = app.get_window(shared::MIRROR_WINDOW_LABEL) {
+		mirror.set_focus()?;
+		return Ok(());
+	}
+	let mirror = tauri::WindowBuilder::new(app, shared::MIRROR_WINDOW_LABEL, tauri::WindowUrl::default())
+		.title("Layered Key Map Display (Mirror)")
+		.resizable(false)
+		.transparent(false)
+		.visible(false)
+		.build()?;
+	// the same size as the overlay, so the layout is drawn the same way
+	if let Some(profile) = app.state::<ConfigMutex>().get().active_profile() {
+		apply_window_size(&mirror, profile)?;
+	}
+	mirror.show()?;
+	log::info!(target: "window", "Opened mirror window");
+	Ok(())
+}
+
+/// Captures the region of the screen covered by the overlay, with whatever it is currently showing,
+/// saving it as a png in the `screenshots` folder of the config folder. Returns None when running headless.
+fn screenshot_overlay(app: &tauri::AppHandle<tauri::Wry>) -> anyhow::Result<Option<std::path::PathBuf>> {
+	let Some(window) = overlay_window(app)? else {
+		return Ok(None);
+	};
+	let Some(config_dir) = tauri::api::path::app_config_dir(&app.config()) else {
+		return Ok(None);
+	};
+	let position = window.outer_position()?;
+	let size = window.outer_size()?;
+	let center = (position.x + size.width as i32 / 2, position.y + size.height as i32 / 2);
+	let monitor = xcap::Monitor::from_point(center.0, center.1)?;
+	let image = monitor.capture_image()?;
+	// the window is positioned in the virtual desktop, but the capture is relative to the monitor
+	let x = (position.x - monitor.x()).max(0) as u32;
+	let y = (position.y - monitor.y()).max(0) as u32;
+	let image = xcap::image::imageops::crop_imm(&image, x, y, size.width, size.height).to_image();
+
+	let screenshots_dir = config_dir.join("screenshots");
+	std::fs::create_dir_all(&screenshots_dir)?;
+	let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
+	let path = screenshots_dir.join(format!("overlay-{}.png", timestamp.as_secs()));
+	image.save(&path)?;
+	log::info!(target: "window", "Saved screenshot of the overlay to {path:?}");
+	Ok(Some(path))
+}
+
+/// The overlay's current `size` and `location` as kdl for a profile, so a window placed by dragging it can be saved.
+/// Returns None when running headless.
+fn window_geometry_kdl(app: &tauri::AppHandle<tauri::Wry>) -> anyhow::Result<Option<String>> {
+	let Some(window) = overlay_window(app)? else {
+		return Ok(None);
+	};
+	let mut monitors = Vec::new();
+	for monitor in window.available_monitors()? {
+		let (pos, size) = (monitor.position(), monitor.size());
+		monitors.push(((pos.x, pos.y), (size.width, size.height)));
+	}
+	let position = window.outer_position()?;
+	let outer_size = window.outer_size()?;
+	let location = WindowPosition::from_window(
+		&monitors,
+		(position.x, position.y),
+		(outer_size.width, outer_size.height),
+	);
+	// profile sizes are scaled when applied to the window, see `apply_window_size`
+	let config = app.state::<ConfigMutex>().get();
+	let scale = config.active_profile().map_or(1.0, |profile| profile.scale);
+	let size = (
+		(outer_size.width as f64 / scale).round() as u32,
+		(outer_size.height as f64 / scale).round() as u32,
+	);
+	Ok(Some(serialize_window_geometry_kdl(size, &location)))
+}
+
+/// The keys and hotkeys which are currently pressed, as seen by the input engine.
+#[tauri::command]
+fn get_input_state(input: tauri::State<'_, GlobalInputState>) -> PressedInput {
+	input.pressed_input()
+}
+
+/// Pauses or resumes reacting to input, keeping the overlay as it was when frozen.
+#[tauri::command]
+fn set_frozen(app: tauri::AppHandle<tauri::Wry>, frozen: bool) {
+	app.state::<GlobalInputState>().set_frozen(frozen);
+	let _ = app.tray_handle().get_item(TRAY_FREEZE.0).set_selected(frozen);
+}
+
+/// Highlights a switch in the overlay for some duration, regardless of what is actually pressed.
+#[tauri::command]
+fn highlight_switch(app: tauri::AppHandle<tauri::Wry>, id: String, duration_ms: u64) -> Result<(), String> {
+	let config = app.state::<ConfigMutex>().get();
+	if !config.layout().switches().contains_key(&id) {
+		return Err(format!("The active layout has no switch named {id:?}"));
+	}
+	let highlight = shared::SwitchHighlight {
+		switch_id: id,
+		duration_ms,
+	};
+	let _ = app.emit_all("highlight", highlight);
+	Ok(())
+}
+
+/// Presses and then releases a switch (or combo) in the overlay, as if its real key was pressed.
+#[tauri::command]
+fn test_switch(
+	app: tauri::AppHandle<tauri::Wry>,
+	switch_id: String,
+	slot: Option<shared::SwitchSlot>,
+) -> Result<(), String> {
+	let config = app.state::<ConfigMutex>().get();
+	let layout = config.layout();
+	let is_combo = layout.combos().iter().any(|combo| combo.id == switch_id);
+	if !layout.switches().contains_key(&switch_id) && !is_combo {
+		return Err(format!("The active layout has no switch or combo named {switch_id:?}"));
+	}
+	let input = app.state::<GlobalInputState>();
+	input.simulate_press(switch_id, slot, TEST_SWITCH_DURATION);
+	Ok(())
+}
+
+/// How often each combo has been attempted and triggered this session, for chord practice.
+#[tauri::command]
+fn get_combo_stats(input: tauri::State<'_, GlobalInputState>) -> BTreeMap<String, ComboStats> {
+	input.combo_stats()
+}
+
+/// The session's combo stats as csv, with a row per combo.
+#[tauri::command]
+fn export_combo_stats_csv(input: tauri::State<'_, GlobalInputState>) -> String {
+	combo_stats_csv(&input.combo_stats())
+}
+
+/// The config as the app parsed and is currently using it, serialized back to kdl.
+/// Unlike the exported config this keeps runtime state, such as the active profile, to debug what was actually loaded.
+#[tauri::command]
+fn dump_resolved_config(config: tauri::State<'_, ConfigMutex>) -> String {
+	serialize_config_kdl(&config.get())
+}
+
+/// Every hotkey the overlay listens for, as pairs of the switch (or combo) id and the hotkey which triggers it.
+#[tauri::command]
+fn list_hotkeys(config: tauri::State<'_, ConfigMutex>) -> Vec<(String, String)> {
+	let hotkeys = config.get().registered_hotkeys().into_iter();
+	hotkeys
+		.map(|(switch_id, hotkey)| (switch_id, hotkey.to_string()))
+		.collect()
+}
+
+/// Activates or deactivates a layer in the overlay without pressing the keys bound to it, such as for screenshots.
+#[tauri::command]
+fn set_layer_active(app: tauri::AppHandle<tauri::Wry>, layer: String, active: bool) -> Result<(), String> {
+	let config = app.state::<ConfigMutex>().get();
+	let layout = config.layout();
+	if layout.get_layer(&layer).is_none() {
+		return Err(format!("The active layout has no layer named {layer:?}"));
+	}
+	// the default layer is always active, other layers are shown on top of it
+	if !active && layer == *layout.default_layer() {
+		return Err(format!("The default layer {layer:?} cannot be deactivated"));
+	}
+	app.state::<GlobalInputState>().set_layer_active(layer, active);
+	Ok(())
+}
+
+/// Checks if a config would be accepted on import, without replacing the current config.
+#[tauri::command]
+fn validate_config(kdl: String) -> Result<(), String> {
+	let config = parse_config_kdl(&kdl).map_err(|err| format!("{err:?}"))?;
+	config.validate().map_err(|err| err.to_string())?;
+	Ok(())
+}
+
+fn reload_config(app: &tauri::AppHandle<tauri::Wry>) {
+	match load_config(&app.config()) {
+		Ok(Some(config)) => {
+			log_config_changes(app, &config);
+			if let Err(err) = set_config(app, config) {
+				log::error!("{err:?}");
+			}
+			set_config_error(app, None);
+		}
+		Ok(None) => {}
+		Err(err) => {
+			log::error!("{err:?}");
+			set_config_error(app, Some(format!("{err:#}")));
+		}
+	}
+}
+
+/// Imports a config, showing a notification of whether it was imported since it is imported from the tray.
 fn upload_config(app: &tauri::AppHandle<tauri::Wry>, contents: &str) -> anyhow::Result<()> {
+	let result = import_config(app, contents);
+	match &result {
+		Ok(summary) => notify(app, "Config imported", summary),
+		Err(err) => notify(app, "Config import failed", &format!("{err:#}")),
+	}
+	result.map(|_summary| ())
+}
+
+/// Parses, saves, and applies a config, returning a summary of what was imported.
+fn import_config(app: &tauri::AppHandle<tauri::Wry>, contents: &str) -> anyhow::Result<String> {
 	let config = parse_config_kdl(contents)?;
-	save_config(&app.config(), &config)?;
+	let profiles = config.iter_profiles().count();
+	let layers = config.layout().layers().len();
+	save_app_config(app, &config)?;
 	set_config(&app, config)?;
-	Ok(())
+	set_config_error(app, None);
+	Ok(format!("{profiles} profiles, {layers} layers"))
 }
 
-fn build_system_tray_menu(config: &Config) -> SystemTrayMenu {
+/// Shows a native notification, for feedback on actions taken from the system tray.
+fn notify(app: &tauri::AppHandle<tauri::Wry>, title: &str, body: &str) {
+	let notification = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier);
+	if let Err(err) = notification.title(title).body(body).notify(app) {
+		log::warn!("Failed to show notification {title:?}: {err:?}");
+	}
+}
+
+/// Logs what a reloaded config changed from the current config, to confirm the reload picked up the edits.
+fn log_config_changes(app: &tauri::AppHandle<tauri::Wry>, config: &Config) {
+	let changes = config.changes_since(&app.state::<ConfigMutex>().get());
+	if changes.is_empty() {
+		log::info!(target: "config", "Reloaded config, nothing changed");
+		return;
+	}
+	log::info!(target: "config", "Reloaded config with {} changes", changes.len());
+	for change in changes {
+		log::info!(target: "config", "- {change}");
+	}
+}
+
+/// Saves the config to the config folder, unless the config in use didn't come from there.
+fn save_app_config(app: &tauri::AppHandle<tauri::Wry>, config: &Config) -> anyhow::Result<()> {
+	let has_config_error = app.state::<ConfigErrorMutex>().get().is_some();
+	if let Some(reason) = skip_save_reason(&app.state::<LaunchArgs>(), has_config_error) {
+		log::debug!(target: "config", "Not saving the config, {reason}");
+		return Ok(());
+	}
+	save_config(&app.config(), config)
+}
+
+/// Why the config in use must not be saved over the config folder's config, if it must not.
+/// A piped config is only kept in memory, and while in safe mode or after a config failed to load
+/// the saved config is the broken one the user needs to fix, so it is never overwritten.
+fn skip_save_reason(args: &LaunchArgs, has_config_error: bool) -> Option<&'static str> {
+	if args.config_from_stdin {
+		return Some("it was read from stdin");
+	}
+	if args.safe_mode {
+		return Some("launched in safe mode");
+	}
+	if has_config_error {
+		return Some("the saved config failed to load");
+	}
+	None
+}
+
+/// Asks whether to save the built-in default config to the config folder, so it can be edited from there.
+fn offer_to_save_config(app: &tauri::AppHandle<tauri::Wry>) {
+	let app = app.clone();
+	tauri::api::dialog::ask(
+		None::<&tauri::Window>,
+		"No config found",
+		"The built-in default layout is being shown. Save it to the config folder so it can be customized?",
+		move |save| {
+			if !save {
+				return;
+			}
+			let config = app.state::<ConfigMutex>().get();
+			if let Err(err) = save_config(&app.config(), &config) {
+				log::error!(target: "config", "Failed to save the default config: {err:?}");
+			}
+		},
+	);
+}
+
+/// Records why the config could not be loaded (or clears it), showing it in a banner on the overlay.
+fn set_config_error(app: &tauri::AppHandle<tauri::Wry>, error: Option<String>) {
+	app.state::<ConfigErrorMutex>().set(error.clone());
+	let _ = app.emit_all("config_error", error);
+}
+
+fn build_system_tray_menu(config: &Config, flags: &shared::DisplayFlags, frozen: bool) -> SystemTrayMenu {
 	let mut menu = SystemTrayMenu::new();
 	menu = menu.add_item(CustomMenuItem::new(MENU_TOGGLE_ID, MENU_TOGGLE_HIDE));
+	menu = menu.add_item(CustomMenuItem::new(TRAY_NEXT_MONITOR.0, TRAY_NEXT_MONITOR.1));
+	menu = menu.add_item(CustomMenuItem::new(TRAY_COPY_GEOMETRY.0, TRAY_COPY_GEOMETRY.1));
+	menu = menu.add_item(CustomMenuItem::new(TRAY_SCREENSHOT.0, TRAY_SCREENSHOT.1));
+	menu = menu.add_item(CustomMenuItem::new(TRAY_MIRROR_WINDOW.0, TRAY_MIRROR_WINDOW.1));
 
 	if config.has_profiles() {
-		menu = menu.add_submenu(SystemTraySubmenu::new(
-			"Profiles",
-			config
-				.iter_profiles()
-				.fold(SystemTrayMenu::new(), |menu, (name, _profile)| {
-					menu.add_item(CustomMenuItem::new(format!("profile:{name}"), name))
-				}),
-		));
+		// Profiles with a group are nested in a submenu per group, after all of the ungrouped profiles
+		let mut profiles_menu = SystemTrayMenu::new();
+		let mut group_menus = BTreeMap::<&String, SystemTrayMenu>::new();
+		for (name, profile) in config.iter_profiles() {
+			let item = CustomMenuItem::new(format!("profile:{name}"), name);
+			match &profile.group {
+				None => profiles_menu = profiles_menu.add_item(item),
+				Some(group) => {
+					let group_menu = group_menus.remove(group).unwrap_or_default();
+					group_menus.insert(group, group_menu.add_item(item));
+				}
+			}
+		}
+		for (group, group_menu) in group_menus {
+			profiles_menu = profiles_menu.add_submenu(SystemTraySubmenu::new(group, group_menu));
+		}
+		profiles_menu = profiles_menu
+			.add_native_item(tauri::SystemTrayMenuItem::Separator)
+			.add_item(CustomMenuItem::new(TRAY_RESET_PROFILE.0, TRAY_RESET_PROFILE.1));
+		menu = menu.add_submenu(SystemTraySubmenu::new("Profiles", profiles_menu));
 	}
 
 	menu.add_native_item(tauri::SystemTrayMenuItem::Separator)
+		.add_item(flag_menu_item(TRAY_FLAG_DESIGN_GRID, flags.design_grid))
+		.add_item(flag_menu_item(TRAY_FLAG_ALL_LAYERS, flags.all_layers))
+		.add_item(flag_menu_item(TRAY_FREEZE, frozen))
+		.add_native_item(tauri::SystemTrayMenuItem::Separator)
 		.add_item(CustomMenuItem::new(TRAY_CONFIG_IMPORT.0, TRAY_CONFIG_IMPORT.1))
 		.add_item(CustomMenuItem::new(TRAY_CONFIG_EXPORT.0, TRAY_CONFIG_EXPORT.1))
+		.add_item(CustomMenuItem::new(TRAY_LAYOUT_EXPORT_SVG.0, TRAY_LAYOUT_EXPORT_SVG.1))
+		.add_item(CustomMenuItem::new(TRAY_COMBO_STATS.0, TRAY_COMBO_STATS.1))
 		.add_item(CustomMenuItem::new(TRAY_CONFIG_RELOAD.0, TRAY_CONFIG_RELOAD.1))
 		.add_item(CustomMenuItem::new(TRAY_CONFIG_OPEN_DIR.0, TRAY_CONFIG_OPEN_DIR.1))
 		.add_native_item(tauri::SystemTrayMenuItem::Separator)
 		.add_item(CustomMenuItem::new(MENU_QUIT.0, MENU_QUIT.1))
 }
 
+fn flag_menu_item((id, title): (&'static str, &'static str), selected: bool) -> CustomMenuItem {
+	let item = CustomMenuItem::new(id, title);
+	match selected {
+		true => item.selected(),
+		false => item,
+	}
+}
+
 fn set_config(app: &tauri::AppHandle<tauri::Wry>, config: Config) -> anyhow::Result<()> {
-	app.emit_all("layout", config.layout().clone())?;
+	for lint in config.lint_layout() {
+		log::warn!(target: "config", "{lint}");
+	}
+	// The layout is the largest payload sent to the frontend, so it is only re-sent when it actually changed.
+	// The frontend asks for it again when it becomes ready, so a skipped emit is never missed.
+	let config_state = app.state::<ConfigMutex>();
+	if config_state.get().layout() != config.layout() {
+		app.emit_all("layout", config.layout().clone())?;
+	}
 
 	let config_payload = serde_json::to_string(&config)?;
-	app.state::<ConfigMutex>().set(config);
+	config_state.set(config);
 	app.trigger_global("config", Some(config_payload.clone()));
 	app.trigger_global("config:profile", Some(config_payload));
 	Ok(())
 }
 
+#[derive(thiserror::Error, Debug)]
+#[error("There is no window labeled {0:?} to display the overlay, check the window_label of the config")]
+struct MissingWindow(String);
+
+/// The window which displays the overlay, as chosen by the config, or None when running headless.
+fn overlay_window(app: &tauri::AppHandle<tauri::Wry>) -> Result<Option<tauri::Window>, MissingWindow> {
+	if app.state::<LaunchArgs>().headless {
+		return Ok(None);
+	}
+	let label = app.state::<ConfigMutex>().get().window_label().to_owned();
+	match app.get_window(&label) {
+		Some(window) => Ok(Some(window)),
+		None => Err(MissingWindow(label)),
+	}
+}
+
 fn apply_initial_window_location(app: &tauri::AppHandle<tauri::Wry>, profile: &DisplayProfile) -> anyhow::Result<()> {
-	let window = app.get_window("main").ok_or(tauri::Error::InvalidWindowHandle)?;
+	let Some(window) = overlay_window(app)? else {
+		return Ok(());
+	};
+	apply_window_size(&window, profile)?;
+	move_window_to_position(&window, profile.location)?;
+	Ok(())
+}
+
+/// Activates the profile which targets the monitor the overlay is currently on, if it isn't already active.
+/// The window keeps the position it was moved to, but takes on the size and scale of the newly active profile.
+fn select_monitor_profile(app: &tauri::AppHandle<tauri::Wry>) -> anyhow::Result<()> {
+	let Some(window) = overlay_window(app)? else {
+		return Ok(());
+	};
+	let Some(monitor) = window.current_monitor()? else {
+		return Ok(());
+	};
+	let Some(monitor_name) = monitor.name() else {
+		return Ok(());
+	};
+	let config_state = app.state::<ConfigMutex>();
+	let mut config = config_state.get();
+	let Some(profile_name) = config.profile_for_monitor(monitor_name).cloned() else {
+		return Ok(());
+	};
+	if config.active_profile_id() == &profile_name {
+		return Ok(());
+	}
+	log::info!(target: "config", "Overlay moved to monitor {monitor_name:?}, switching to profile {profile_name:?}");
+	config.set_active_profile(&profile_name)?;
+	save_app_config(app, &config)?;
+	let profile = config.active_profile().cloned().unwrap_or_default();
+	config_state.set(config);
+
+	apply_window_size(&window, &profile)?;
+	app.emit_all("scale", profile.scale)?;
+	app.emit_all("profile_layers", profile.layers)?;
+	Ok(())
+}
 
+/// Resizes the overlay to keep the aspect ratio of the layout, if the active profile locks its aspect.
+fn lock_window_aspect(app: &tauri::AppHandle<tauri::Wry>, size: (u32, u32)) -> anyhow::Result<()> {
+	let Some(window) = overlay_window(app)? else {
+		return Ok(());
+	};
+	let config = app.state::<ConfigMutex>().get();
+	let Some(profile) = config.active_profile() else {
+		return Ok(());
+	};
+	let Some((width, height)) = profile.aspect_locked_size(config.layout(), size) else {
+		return Ok(());
+	};
+	window.set_size(tauri::PhysicalSize::<u32> { width, height })?;
+	Ok(())
+}
+
+fn apply_window_size(window: &tauri::Window, profile: &DisplayProfile) -> anyhow::Result<()> {
 	window.set_size(tauri::PhysicalSize::<u32> {
 		width: (profile.size.0 as f64 * profile.scale).floor() as u32,
 		height: (profile.size.1 as f64 * profile.scale).floor() as u32,
 	})?;
-
-	move_window_to_position(&window, profile.location)?;
-
 	Ok(())
 }
 
 fn move_window_to_position(window: &tauri::Window, position: WindowPosition) -> anyhow::Result<()> {
-	// Move the window to the correct monitor
+	// Find the monitor to place the window on, falling back to the first monitor if it doesn't exist
 	let monitors = window.available_monitors()?;
-	let monitor = usize::min(position.monitor, monitors.len());
-	if let Some(monitor) = monitors.get(monitor) {
-		window.set_position(monitor.position().clone())?;
-	}
-	// Anchor it relative to that monitor
-	window.move_window(position.anchor.into())?;
-	// And offset it from the anchor by some amount
-	window.set_position({
-		let mut pos = window.outer_position()?;
-		pos.x += position.offset.0;
-		pos.y -= position.offset.1;
-		pos
-	})?;
+	let monitor = match monitors.get(position.monitor).or(monitors.first()) {
+		Some(monitor) => monitor.clone(),
+		None => window.current_monitor()?.ok_or(tauri::Error::InvalidWindowHandle)?,
+	};
+	// Anchor the window's own edges to the monitor's edges, accounting for the window's outer size (including decorations)
+	let window_size = window.outer_size()?;
+	let (x, y) = position.anchor.window_position(
+		(monitor.position().x, monitor.position().y),
+		(monitor.size().width, monitor.size().height),
+		(window_size.width, window_size.height),
+	);
+	// And offset it from the anchor by some amount (positive y moves the window up)
+	window.set_position(tauri::PhysicalPosition::new(
+		x + position.offset.0,
+		y - position.offset.1,
+	))?;
 	Ok(())
 }
 
@@ -559,3 +1895,262 @@ where
 		log::error!(target: target, "{err:?}");
 	});
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	// the config benchmarked by `benches/handle.rs`, which is also a realistic config for testing input handling
+	static BENCH_CONFIG: &str = include_str!("../benches/typing.kdl");
+
+	/// Input state with the bindings of a config.
+	fn input_for(kdl: &str) -> GlobalInputState {
+		let config = parse_config_kdl(kdl).expect("failed to parse config");
+		let input = GlobalInputState::default();
+		input.update_bindings(&config);
+		input
+	}
+
+	/// Handles an event as if it was just received from the os.
+	fn send(input: &GlobalInputState, event_type: rdev::EventType) {
+		input.handle(&rdev::Event {
+			time: std::time::SystemTime::now(),
+			name: None,
+			event_type,
+		});
+	}
+
+	#[test]
+	fn full_queue_keeps_releases() {
+		let (send_event, recv_event) = std::sync::mpsc::sync_channel::<rdev::Event>(1);
+		let event = |event_type| rdev::Event {
+			time: std::time::SystemTime::now(),
+			name: None,
+			event_type,
+		};
+		queue_event(&send_event, event(rdev::EventType::KeyPress(rdev::Key::KeyA)));
+		// the queue is full, so the repeated press is dropped and the release waits for the press to be processed
+		queue_event(&send_event, event(rdev::EventType::KeyPress(rdev::Key::KeyA)));
+		let release = std::thread::spawn(move || {
+			queue_event(&send_event, event(rdev::EventType::KeyRelease(rdev::Key::KeyA)));
+		});
+		let received = recv_event.iter().map(|event| event.event_type).collect::<Vec<_>>();
+		release.join().expect("release thread panicked");
+		assert_eq!(
+			received,
+			vec![
+				rdev::EventType::KeyPress(rdev::Key::KeyA),
+				rdev::EventType::KeyRelease(rdev::Key::KeyA)
+			]
+		);
+	}
+
+	#[test]
+	fn never_saves_over_unloaded_config() {
+		let args = LaunchArgs::default();
+		assert_eq!(skip_save_reason(&args, false), None);
+		assert!(skip_save_reason(&args, true).is_some());
+		let stdin = LaunchArgs {
+			config_from_stdin: true,
+			..Default::default()
+		};
+		assert!(skip_save_reason(&stdin, false).is_some());
+		let safe_mode = LaunchArgs {
+			safe_mode: true,
+			..Default::default()
+		};
+		assert!(skip_save_reason(&safe_mode, false).is_some());
+	}
+
+	#[test]
+	fn layer_binding_priority() {
+		let kdl = r#"
+default_profile "default"
+layout {
+	default_layer "base"
+	switch "shift" 0 0
+	switch "thumb" 0 -100
+	switch "macro" 50 0
+	layer "base" {
+		bind "shift" {
+			slot "Tap" "F13"
+		}
+		bind "thumb" {
+			slot "Hold" "F13" layer="nav"
+		}
+		bind "macro" {
+			slot "Tap" "F13" priority=5
+		}
+	}
+	layer "nav" {
+		bind "shift" {
+			slot "Tap" "ArrowLeft"
+		}
+	}
+}
+"#;
+		let config = parse_config_kdl(kdl).expect("failed to parse config");
+		let input = GlobalInputState::default();
+		input.update_bindings(&config);
+
+		let f13 = "F13".parse::<shared::KeySet>().expect("failed to parse key set");
+		let hotkeys = alias_hotkeys(&f13, &config.keymap());
+		let state = input.read();
+		let bindings = state.hotkey_bindings.get_vec(&hotkeys[0]).expect("F13 should be bound");
+		let mut changed_bindings = bindings.iter().map(|binding| (true, binding, ())).collect::<Vec<_>>();
+		sort_by_priority(&mut changed_bindings);
+		let order = changed_bindings
+			.iter()
+			.map(|(_, binding, _)| binding.switch_id.as_str())
+			.collect::<Vec<_>>();
+		// explicit priorities come first, then the layer is activated before the switch bound to the same key is pressed
+		assert_eq!(order, vec!["macro", "thumb", "shift"]);
+	}
+
+	#[test]
+	fn overlapping_hotkeys() {
+		let kdl = r#"
+default_profile "default"
+layout {
+	default_layer "base"
+	switch "a" 0 0
+	switch "b" 50 0
+	switch "c" 100 0
+	switch "thumb" 0 -100
+	layer "base" {
+		bind "a" {
+			slot "Tap" "LShift+2"
+		}
+		bind "b" {
+			slot "Tap" "LShift+2"
+		}
+		bind "thumb" {
+			slot "Hold" "F13" layer="nav"
+		}
+	}
+	layer "nav" {
+		bind "c" {
+			slot "Tap" "LShift+2"
+		}
+		bind "a" {
+			slot "Tap" "F13"
+		}
+	}
+}
+"#;
+		let input = input_for(kdl);
+
+		let state = input.read();
+		let mut overlaps = Vec::new();
+		for (_hotkey, binding, other) in state.overlapping_hotkeys() {
+			let mut pair = [binding.switch_id.as_str(), other.switch_id.as_str()];
+			pair.sort();
+			overlaps.push(pair);
+		}
+		overlaps.dedup();
+		// the switch bound on another layer, and the layer sharing a key with a switch, aren't overlaps
+		assert_eq!(overlaps, vec![["a", "b"]]);
+	}
+
+	#[test]
+	fn combo_stats() {
+		let input = input_for(BENCH_CONFIG);
+		// a partial attempt, only pressing one of the keys
+		send(&input, rdev::EventType::KeyPress(rdev::Key::KeyJ));
+		send(&input, rdev::EventType::KeyRelease(rdev::Key::KeyJ));
+		// a successful attempt
+		send(&input, rdev::EventType::KeyPress(rdev::Key::KeyK));
+		send(&input, rdev::EventType::KeyPress(rdev::Key::KeyJ));
+		send(&input, rdev::EventType::KeyRelease(rdev::Key::KeyJ));
+		send(&input, rdev::EventType::KeyRelease(rdev::Key::KeyK));
+
+		let stats = input.combo_stats();
+		let expected = ComboStats {
+			attempts: 2,
+			successes: 1,
+			timed_out: 0,
+		};
+		assert_eq!(stats.get("esc"), Some(&expected));
+		assert_eq!(
+			combo_stats_csv(&stats),
+			"combo,attempts,successes,timed_out,accuracy\nesc,2,1,0,0.500\n"
+		);
+	}
+
+	#[test]
+	fn layer_without_passthrough() {
+		let kdl = BENCH_CONFIG.replace(r#"layer "nav" {"#, r#"layer "nav" passthrough=false {"#);
+		let input = input_for(&kdl);
+		let press = |key| send(&input, rdev::EventType::KeyPress(key));
+		// F13 activates nav
+		press(rdev::Key::Unknown(124));
+		// "A" is only bound by the base layer, which nav blocks
+		press(rdev::Key::KeyA);
+		// "Left" is bound by nav itself
+		press(rdev::Key::LeftArrow);
+		let state = input.active_state();
+		assert_eq!(state.active_layers, vec!["base".to_owned(), "nav".to_owned()]);
+		assert_eq!(state.active_switches, vec!["l_index".to_owned(), "l_thumb".to_owned()]);
+	}
+
+	#[test]
+	fn combo_lockout() {
+		let kdl = BENCH_CONFIG
+			.replace("layout {", "layout {\n\tcombo_lockout_ms 500")
+			.replace(
+				r#"bind "J+K""#,
+				"link {\n\t\t\tswitch \"r_index\" 0 1\n\t\t\tanchor 0 -1\n\t\t}\n\t\tbind \"J+K\"",
+			);
+		let input = input_for(&kdl);
+		send(&input, rdev::EventType::KeyPress(rdev::Key::KeyJ));
+		send(&input, rdev::EventType::KeyPress(rdev::Key::KeyK));
+		send(&input, rdev::EventType::KeyRelease(rdev::Key::KeyJ));
+		send(&input, rdev::EventType::KeyRelease(rdev::Key::KeyK));
+		// "J" is bound to r_index, which is linked to the combo that was just released
+		send(&input, rdev::EventType::KeyPress(rdev::Key::KeyJ));
+		assert_eq!(input.active_state().active_switches, Vec::<String>::new());
+		send(&input, rdev::EventType::KeyRelease(rdev::Key::KeyJ));
+		// "K" is bound to r_middle, which isn't linked to the combo
+		send(&input, rdev::EventType::KeyPress(rdev::Key::KeyK));
+		assert_eq!(input.active_state().active_switches, vec!["r_middle".to_owned()]);
+	}
+
+	#[test]
+	fn layer_suppressing_default() {
+		let kdl = BENCH_CONFIG.replace(r#"layer "nav" {"#, r#"layer "nav" suppresses_default=true {"#);
+		let input = input_for(&kdl);
+		let press = |key| send(&input, rdev::EventType::KeyPress(key));
+		// "J" is bound by the base layer, which is active until nav is
+		press(rdev::Key::KeyJ);
+		assert_eq!(input.active_state().active_switches, vec!["r_index".to_owned()]);
+		// F13 activates nav, which suppresses base
+		press(rdev::Key::Unknown(124));
+		// "Enter" is bound by the base layer on a switch nav doesn't bind, so it would otherwise pass through
+		press(rdev::Key::Return);
+		// "Left" is bound by nav itself
+		press(rdev::Key::LeftArrow);
+		let state = input.active_state();
+		assert_eq!(
+			state.active_switches,
+			vec!["l_index".to_owned(), "l_thumb".to_owned(), "r_index".to_owned()]
+		);
+	}
+
+	#[test]
+	fn layer_step() {
+		let kdl = BENCH_CONFIG.replace(
+			"layout {",
+			"layer_step_next \"F12\"\nlayer_step_previous \"F11\"\nlayout {",
+		);
+		let input = input_for(&kdl);
+		let tap = |key| {
+			send(&input, rdev::EventType::KeyPress(key));
+			send(&input, rdev::EventType::KeyRelease(key));
+			input.active_state().active_layers
+		};
+		assert_eq!(tap(rdev::Key::F12), vec!["base".to_owned(), "nav".to_owned()]);
+		// stepping past the last layer shows only the default layer
+		assert_eq!(tap(rdev::Key::F12), vec!["base".to_owned()]);
+		assert_eq!(tap(rdev::Key::F11), vec!["base".to_owned(), "nav".to_owned()]);
+	}
+}
@@ -0,0 +1,32 @@
+/// Command line flags provided when launching the application.
+#[derive(Clone, Debug, Default)]
+pub struct LaunchArgs {
+	/// Read the initial config from standard input instead of the config directory.
+	pub config_from_stdin: bool,
+	/// Serve the active input state over http on this localhost port.
+	pub state_port: Option<u16>,
+	/// Ignore the saved config and launch with the default config.
+	pub safe_mode: bool,
+	/// Never touch the overlay window, so input and config handling can run (e.g. in tests) without a display.
+	pub headless: bool,
+}
+
+impl LaunchArgs {
+	pub fn from_env() -> Self {
+		let mut args = Self::default();
+		let mut env_args = std::env::args().skip(1);
+		while let Some(arg) = env_args.next() {
+			match arg.as_str() {
+				"--stdin" => args.config_from_stdin = true,
+				"--safe-mode" => args.safe_mode = true,
+				"--headless" => args.headless = true,
+				"--state-port" => match env_args.next().map(|port| port.parse::<u16>()) {
+					Some(Ok(port)) => args.state_port = Some(port),
+					_ => log::warn!(target: "args", "Expected a port number after {arg:?}"),
+				},
+				_ => log::warn!(target: "args", "Ignoring unknown argument {arg:?}"),
+			}
+		}
+		args
+	}
+}
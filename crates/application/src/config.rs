@@ -1,5 +1,8 @@
 use derivative::Derivative;
-use kdlize::{ext::DocumentExt, AsKdl, FromKdl, OmitIfEmpty};
+use kdlize::{
+	ext::{DocumentExt, ValueExt},
+	AsKdl, FromKdl, OmitIfEmpty,
+};
 use serde::{Deserialize, Serialize};
 use std::{
 	collections::{BTreeMap, HashSet},
@@ -18,11 +21,33 @@ impl ConfigMutex {
 	}
 }
 
+/// The label of the overlay window declared in `tauri.conf.json`, used when the config doesn't choose another.
+pub static DEFAULT_WINDOW_LABEL: &str = "main";
+
+/// Hash of the contents most recently written by `save_config`, so the config watcher can ignore the app's own writes.
+static LAST_SAVED_HASH: Mutex<Option<u64>> = Mutex::new(None);
+
+fn hash_contents(contents: &str) -> u64 {
+	use std::hash::{Hash, Hasher};
+	let mut hasher = std::collections::hash_map::DefaultHasher::new();
+	contents.hash(&mut hasher);
+	hasher.finish()
+}
+
+/// Returns true if the contents are exactly what the app last saved to the config file.
+pub fn is_saved_config(contents: &str) -> bool {
+	*LAST_SAVED_HASH.lock().unwrap() == Some(hash_contents(contents))
+}
+
+pub fn config_file_path(app_config: &tauri::Config) -> Option<std::path::PathBuf> {
+	let config_dir = tauri::api::path::app_config_dir(&app_config)?;
+	Some(config_dir.join("config.kdl"))
+}
+
 pub fn load_config(app_config: &tauri::Config) -> anyhow::Result<Option<Config>> {
-	let Some(config_path) = tauri::api::path::app_config_dir(&app_config) else {
+	let Some(config_path) = config_file_path(app_config) else {
 		return Ok(None);
 	};
-	let config_path = config_path.join("config.kdl");
 	if !config_path.exists() {
 		return Ok(None);
 	}
@@ -31,6 +56,21 @@ pub fn load_config(app_config: &tauri::Config) -> anyhow::Result<Option<Config>>
 	Ok(Some(config))
 }
 
+/// The config used when none has been saved yet, so the overlay shows something useful on first run.
+static DEFAULT_CONFIG_KDL: &str = include_str!("default_config.kdl");
+
+pub fn default_config() -> anyhow::Result<Config> {
+	let config = parse_config_kdl(DEFAULT_CONFIG_KDL)?;
+	Ok(config)
+}
+
+pub fn read_config_stdin() -> anyhow::Result<Config> {
+	let mut config_str = String::new();
+	std::io::Read::read_to_string(&mut std::io::stdin(), &mut config_str)?;
+	let config = parse_config_kdl(&config_str)?;
+	Ok(config)
+}
+
 pub fn parse_config_kdl(config_str: &str) -> Result<Config, <Config as FromKdl<()>>::Error> {
 	let config_doc = config_str.parse::<kdl::KdlDocument>()?;
 	let mut doc_node = kdl::KdlNode::new("document");
@@ -46,13 +86,30 @@ pub fn serialize_config_kdl(config: &Config) -> String {
 	contents
 }
 
+/// Serializes a window's `size` and `location` as kdl which can be pasted into a profile.
+pub fn serialize_window_geometry_kdl(size: (u32, u32), location: &WindowPosition) -> String {
+	let mut node = kdlize::NodeBuilder::default();
+	node.child({
+		let mut node = kdlize::NodeBuilder::default();
+		node.entry(size.0 as i64);
+		node.entry(size.1 as i64);
+		node.build("size")
+	});
+	node.child(("location", location));
+	let contents = node.into_document().to_string();
+	let contents = contents.replace("    ", "\t");
+	contents
+}
+
 pub fn save_config(app_config: &tauri::Config, config: &Config) -> anyhow::Result<()> {
 	let Some(config_path) = tauri::api::path::app_config_dir(&app_config) else {
 		return Ok(());
 	};
 	std::fs::create_dir_all(&config_path)?;
 	let config_path = config_path.join("config.kdl");
-	std::fs::write(config_path, serialize_config_kdl(config))?;
+	let contents = serialize_config_kdl(config);
+	*LAST_SAVED_HASH.lock().unwrap() = Some(hash_contents(&contents));
+	std::fs::write(config_path, contents)?;
 	Ok(())
 }
 
@@ -62,6 +119,14 @@ pub struct Config {
 	active_profile: String,
 	profiles: BTreeMap<String, DisplayProfile>,
 	layout: shared::Layout,
+	keyboard_locale: KeyboardLocale,
+	// raw key codes for aliases which rdev reports only as `Unknown(code)` on the user's keyboard
+	scancode_aliases: BTreeMap<shared::KeyAlias, u32>,
+	// the label of the window which displays the overlay, if not the default window
+	window_label: Option<String>,
+	// global hotkeys which step through the layers in `layer_order`, for demoing a keymap one layer at a time
+	layer_step_next: Option<shared::KeySet>,
+	layer_step_previous: Option<shared::KeySet>,
 }
 
 impl Default for Config {
@@ -69,20 +134,13 @@ impl Default for Config {
 		Self {
 			default_profile: "default".into(),
 			active_profile: "default".into(),
-			profiles: [(
-				"default".into(),
-				DisplayProfile {
-					size: (800, 600),
-					scale: 1.0,
-					location: WindowPosition {
-						anchor: WindowAnchor::Center,
-						monitor: 0,
-						offset: (0, 0),
-					},
-				},
-			)]
-			.into(),
+			profiles: [("default".into(), DisplayProfile::default())].into(),
 			layout: shared::Layout::default(),
+			keyboard_locale: KeyboardLocale::default(),
+			scancode_aliases: BTreeMap::new(),
+			window_label: None,
+			layer_step_next: None,
+			layer_step_previous: None,
 		}
 	}
 }
@@ -92,10 +150,18 @@ impl Config {
 		&self.default_profile
 	}
 
+	pub fn active_profile_id(&self) -> &String {
+		&self.active_profile
+	}
+
 	pub fn active_profile(&self) -> Option<&DisplayProfile> {
 		self.profile(&self.active_profile)
 	}
 
+	pub fn active_profile_mut(&mut self) -> Option<&mut DisplayProfile> {
+		self.profiles.get_mut(&self.active_profile)
+	}
+
 	pub fn set_active_profile(&mut self, name: impl AsRef<str>) -> Result<(), anyhow::Error> {
 		if !self.profiles.contains_key(name.as_ref()) {
 			return Err(anyhow::Error::msg("Invalid profile name"));
@@ -104,6 +170,13 @@ impl Config {
 		Ok(())
 	}
 
+	/// The name of the profile which targets the monitor named `monitor_name`, if any profile does.
+	pub fn profile_for_monitor(&self, monitor_name: &str) -> Option<&String> {
+		let mut profiles = self.profiles.iter();
+		let (name, _) = profiles.find(|(_, profile)| profile.target_monitor.as_deref() == Some(monitor_name))?;
+		Some(name)
+	}
+
 	pub fn has_profiles(&self) -> bool {
 		!self.profiles.is_empty()
 	}
@@ -116,13 +189,288 @@ impl Config {
 		self.profiles.get(key.as_ref())
 	}
 
+	pub fn add_profile(&mut self, name: impl Into<String>, profile: DisplayProfile) -> Result<(), ProfileError> {
+		let name = name.into();
+		if self.profiles.contains_key(&name) {
+			return Err(ProfileError::AlreadyExists(name));
+		}
+		self.profiles.insert(name, profile);
+		Ok(())
+	}
+
+	/// Removes the named profile. The default profile cannot be removed,
+	/// and removing the active profile will make the default profile active.
+	pub fn remove_profile(&mut self, name: impl AsRef<str>) -> Result<DisplayProfile, ProfileError> {
+		let name = name.as_ref();
+		if *name == self.default_profile {
+			return Err(ProfileError::RemoveDefault(name.to_owned()));
+		}
+		let Some(profile) = self.profiles.remove(name) else {
+			return Err(ProfileError::Missing(name.to_owned()));
+		};
+		if *name == self.active_profile {
+			self.active_profile = self.default_profile.clone();
+		}
+		Ok(profile)
+	}
+
+	/// Resets the geometry (size, location, and scale) of the named profile to the defaults,
+	/// such as when it was moved off-screen. Its group, layers, and target monitor are kept.
+	pub fn reset_profile(&mut self, name: impl AsRef<str>) -> Result<(), ProfileError> {
+		let name = name.as_ref();
+		let Some(profile) = self.profiles.get_mut(name) else {
+			return Err(ProfileError::Missing(name.to_owned()));
+		};
+		let defaults = DisplayProfile::default();
+		profile.size = defaults.size;
+		profile.location = defaults.location;
+		profile.scale = defaults.scale;
+		Ok(())
+	}
+
+	/// Renames a profile, updating the default and active profiles if they refer to it.
+	pub fn rename_profile(&mut self, old: impl AsRef<str>, new: impl Into<String>) -> Result<(), ProfileError> {
+		let old = old.as_ref();
+		let new = new.into();
+		if old == new {
+			return match self.profiles.contains_key(old) {
+				true => Ok(()),
+				false => Err(ProfileError::Missing(old.to_owned())),
+			};
+		}
+		if self.profiles.contains_key(&new) {
+			return Err(ProfileError::AlreadyExists(new));
+		}
+		let Some(profile) = self.profiles.remove(old) else {
+			return Err(ProfileError::Missing(old.to_owned()));
+		};
+		if *old == self.default_profile {
+			self.default_profile = new.clone();
+		}
+		if *old == self.active_profile {
+			self.active_profile = new.clone();
+		}
+		self.profiles.insert(new, profile);
+		Ok(())
+	}
+
 	pub fn layout(&self) -> &shared::Layout {
 		&self.layout
 	}
 
+	/// How the key aliases of bindings resolve to the key codes reported when keys are pressed.
+	pub fn keymap(&self) -> Keymap {
+		Keymap {
+			locale: self.keyboard_locale,
+			scancodes: self.scancode_aliases.clone(),
+		}
+	}
+
+	/// The label of the window which displays the overlay.
+	pub fn window_label(&self) -> &str {
+		self.window_label.as_deref().unwrap_or(DEFAULT_WINDOW_LABEL)
+	}
+
+	/// The hotkeys which step the demoed layer forward or backward through the layer order, if configured.
+	pub fn layer_step_hotkeys(&self) -> impl Iterator<Item = (LayerStep, &shared::KeySet)> + '_ {
+		let next = self.layer_step_next.iter().map(|keys| (LayerStep::Next, keys));
+		let previous = self.layer_step_previous.iter().map(|keys| (LayerStep::Previous, keys));
+		next.chain(previous)
+	}
+
+	/// Every hotkey the overlay listens for with this config, paired with the id of the switch (or combo) it triggers.
+	/// These are the same hotkeys the input state registers, so the config can be audited without running the app.
+	/// Chords (key sets with several non-modifier keys) are matched by holding all of their keys rather than as a
+	/// hotkey, so they aren't included.
+	pub fn registered_hotkeys(&self) -> Vec<(String, HotKey)> {
+		let keymap = self.keymap();
+		let layer_bindings = self.layout.layers().values().flat_map(|layer| layer.bindings());
+		let layer_inputs = layer_bindings.flat_map(|(switch_id, bindings)| {
+			let slots = bindings.slots.values();
+			slots.map(move |binding| (switch_id, binding.detected_input()))
+		});
+		let combo_inputs = self.layout.combos().iter().map(|combo| (&combo.id, &combo.input));
+		let mut hotkeys = Vec::new();
+		for (switch_id, keys) in layer_inputs.chain(combo_inputs) {
+			if chord_keys(keys, &keymap).is_some() {
+				continue;
+			}
+			for hotkey in alias_hotkeys(keys, &keymap) {
+				let registered = (switch_id.clone(), hotkey);
+				// switches bound the same way on several layers only listen for each hotkey once
+				if !hotkeys.contains(&registered) {
+					hotkeys.push(registered);
+				}
+			}
+		}
+		hotkeys
+	}
+
 	pub fn clear_state(&mut self) {
 		self.active_profile.clear();
 	}
+
+	/// Describes what changed from the `previous` config, one line per change, to confirm what a reload picked up.
+	pub fn changes_since(&self, previous: &Config) -> Vec<String> {
+		let mut changes = Vec::new();
+		for (name, profile) in &self.profiles {
+			match previous.profiles.get(name) {
+				None => changes.push(format!("added profile {name:?}")),
+				Some(previous) if previous != profile => changes.push(format!("changed profile {name:?}")),
+				Some(_) => {}
+			}
+		}
+		for name in previous.profiles.keys() {
+			if !self.profiles.contains_key(name) {
+				changes.push(format!("removed profile {name:?}"));
+			}
+		}
+		if self.default_profile != previous.default_profile {
+			changes.push(format!("default profile is now {:?}", self.default_profile));
+		}
+		if self.keyboard_locale != previous.keyboard_locale {
+			changes.push(format!("keyboard locale is now {:?}", self.keyboard_locale));
+		}
+		if self.scancode_aliases != previous.scancode_aliases {
+			changes.push(format!("{} scancode aliases", self.scancode_aliases.len()));
+		}
+		if self.window_label != previous.window_label {
+			changes.push(format!("window label is now {:?}", self.window_label()));
+		}
+
+		let (layout, previous_layout) = (&self.layout, &previous.layout);
+		for (layer_id, layer) in layout.layers() {
+			let bindings = layer.bindings().len();
+			match previous_layout.get_layer(layer_id) {
+				None => changes.push(format!("added layer {layer_id:?} with {bindings} bindings")),
+				Some(previous) if previous == layer => {}
+				Some(previous) => {
+					let previous_bindings = previous.bindings().len();
+					changes.push(format!(
+						"changed layer {layer_id:?} ({previous_bindings} -> {bindings} bindings)"
+					));
+				}
+			}
+		}
+		for layer_id in previous_layout.layers().keys() {
+			if layout.get_layer(layer_id).is_none() {
+				changes.push(format!("removed layer {layer_id:?}"));
+			}
+		}
+		if layout.switches() != previous_layout.switches() {
+			let (count, previous_count) = (layout.switches().len(), previous_layout.switches().len());
+			changes.push(format!("changed switches ({previous_count} -> {count})"));
+		}
+		if layout.combos() != previous_layout.combos() {
+			let (count, previous_count) = (layout.combos().len(), previous_layout.combos().len());
+			changes.push(format!("changed combos ({previous_count} -> {count})"));
+		}
+		// anything else in the layout is one of its display settings
+		if changes.is_empty() && layout != previous_layout {
+			changes.push("changed layout settings".into());
+		}
+		changes
+	}
+
+	/// Checks that everything the config refers to by name (profiles, layers, and switches) actually exists.
+	pub fn validate(&self) -> Result<(), ValidationError> {
+		if !self.profiles.contains_key(&self.default_profile) {
+			return Err(ValidationError::MissingDefaultProfile(self.default_profile.clone()));
+		}
+
+		let layout = &self.layout;
+		let layer_exists = |layer_id: &String| layout.get_layer(layer_id).is_some();
+		if !layout.layers().is_empty() && !layer_exists(layout.default_layer()) {
+			return Err(ValidationError::MissingDefaultLayer(layout.default_layer().clone()));
+		}
+		layout.validate_layer_order()?;
+		for (layer_id, layer) in layout.layers() {
+			for (switch_id, bindings) in layer.bindings() {
+				if !layout.switches().contains_key(switch_id) {
+					return Err(ValidationError::UnknownSwitch {
+						layer: layer_id.clone(),
+						switch: switch_id.clone(),
+					});
+				}
+				if let Some(target) = bindings.also_layers.iter().find(|target| !layer_exists(target)) {
+					return Err(ValidationError::UnknownSharedLayer {
+						layer: layer_id.clone(),
+						switch: switch_id.clone(),
+						target: target.clone(),
+					});
+				}
+				for binding in bindings.slots.values() {
+					let Some(target) = &binding.layer else { continue };
+					if !layer_exists(target) {
+						return Err(ValidationError::UnknownTargetLayer {
+							layer: layer_id.clone(),
+							switch: switch_id.clone(),
+							target: target.clone(),
+						});
+					}
+				}
+			}
+		}
+		for combo in layout.combos() {
+			let mut layers = combo.layers.iter().chain(combo.input_layer.iter());
+			if let Some(layer) = layers.find(|layer| !layer_exists(layer)) {
+				return Err(ValidationError::ComboUnknownLayer {
+					combo: combo.id.clone(),
+					layer: layer.clone(),
+				});
+			}
+		}
+		if let Some(lint) = self.lint_layout().into_iter().next() {
+			return Err(lint);
+		}
+		Ok(())
+	}
+
+	/// Finds every combo link which points at a switch that doesn't exist, which the overlay would otherwise
+	/// silently skip drawing. Reported when a config is loaded, so typos in switch ids are caught right away.
+	pub fn lint_layout(&self) -> Vec<ValidationError> {
+		let layout = &self.layout;
+		let mut lints = Vec::new();
+		for combo in layout.combos() {
+			for switch_id in combo.member_switches() {
+				if !layout.switches().contains_key(switch_id) {
+					lints.push(ValidationError::ComboUnknownSwitch {
+						combo: combo.id.clone(),
+						switch: switch_id.clone(),
+					});
+				}
+			}
+		}
+		lints
+	}
+}
+
+#[derive(thiserror::Error, Clone, Debug, PartialEq)]
+pub enum ValidationError {
+	#[error("The default profile {0:?} does not exist")]
+	MissingDefaultProfile(String),
+	#[error("The default layer {0:?} does not exist")]
+	MissingDefaultLayer(String),
+	#[error("Layer {layer:?} binds unknown switch {switch:?}")]
+	UnknownSwitch { layer: String, switch: String },
+	#[error("Switch {switch:?} on layer {layer:?} activates unknown layer {target:?}")]
+	UnknownTargetLayer {
+		layer: String,
+		switch: String,
+		target: String,
+	},
+	#[error("Switch {switch:?} on layer {layer:?} is also bound on unknown layer {target:?}")]
+	UnknownSharedLayer {
+		layer: String,
+		switch: String,
+		target: String,
+	},
+	#[error("Combo {combo:?} refers to unknown layer {layer:?}")]
+	ComboUnknownLayer { combo: String, layer: String },
+	#[error("Combo {combo:?} links to unknown switch {switch:?}")]
+	ComboUnknownSwitch { combo: String, switch: String },
+	#[error(transparent)]
+	LayerOrder(#[from] shared::LayerOrderMismatch),
 }
 
 impl FromKdl<()> for Config {
@@ -142,13 +490,32 @@ impl FromKdl<()> for Config {
 			profiles.insert(name, layer);
 		}
 
-		let layout = node.query_req_t("scope() > layout")?;
+		let layout = shared::Layout::parse(&mut node.query_req("scope() > layout")?)?;
+		let keyboard_locale = node
+			.query_str_opt_t("scope() > keyboard_locale", 0)?
+			.unwrap_or_default();
+
+		let mut scancode_aliases = BTreeMap::new();
+		for mut node in node.query_all("scope() > scancode_alias > code")? {
+			let code = node.next_i64_req()? as u32;
+			let alias = node.next_str_req_t::<shared::KeyAlias>()?;
+			scancode_aliases.insert(alias, code);
+		}
+
+		let window_label = node.query_str_opt("scope() > window_label", 0)?.map(str::to_owned);
+		let layer_step_next = node.query_str_opt_t("scope() > layer_step_next", 0)?;
+		let layer_step_previous = node.query_str_opt_t("scope() > layer_step_previous", 0)?;
 
 		Ok(Self {
 			default_profile,
 			active_profile,
 			profiles,
 			layout,
+			keyboard_locale,
+			scancode_aliases,
+			window_label,
+			layer_step_next,
+			layer_step_previous,
 		})
 	}
 }
@@ -158,6 +525,28 @@ impl AsKdl for Config {
 		let mut node = kdlize::NodeBuilder::default();
 		node.child(("default_profile", &self.default_profile));
 		node.child(("active_profile", &self.active_profile, OmitIfEmpty));
+		if self.keyboard_locale != KeyboardLocale::default() {
+			node.child(("keyboard_locale", &self.keyboard_locale.to_string()));
+		}
+		if !self.scancode_aliases.is_empty() {
+			let mut scancodes = kdlize::NodeBuilder::default();
+			for (alias, code) in &self.scancode_aliases {
+				let mut node = kdlize::NodeBuilder::default();
+				node.entry(*code as i64);
+				node.entry(alias.to_string());
+				scancodes.child(node.build("code"));
+			}
+			node.child(scancodes.build("scancode_alias"));
+		}
+		if let Some(window_label) = &self.window_label {
+			node.child(("window_label", window_label));
+		}
+		if let Some(keys) = &self.layer_step_next {
+			node.child(("layer_step_next", &keys.to_string()));
+		}
+		if let Some(keys) = &self.layer_step_previous {
+			node.child(("layer_step_previous", &keys.to_string()));
+		}
 		for (name, profile) in &self.profiles {
 			node.child(("profile", &(name, profile)));
 		}
@@ -166,11 +555,104 @@ impl AsKdl for Config {
 	}
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+/// Which way a layer step hotkey moves through the layer order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayerStep {
+	Next,
+	Previous,
+}
+
+impl LayerStep {
+	/// The layer to demo after stepping from `current`, skipping the default layer (which is always shown).
+	/// Stepping past either end of the order returns None, showing only the default layer until stepped again.
+	pub fn step<'a>(
+		self,
+		layer_order: &'a [String],
+		default_layer: &String,
+		current: Option<&String>,
+	) -> Option<&'a String> {
+		let mut layers = layer_order.iter().collect::<Vec<_>>();
+		layers.retain(|layer| *layer != default_layer);
+		let index = current.and_then(|current| layers.iter().position(|layer| *layer == current));
+		let index = match (self, index) {
+			(Self::Next, None) => Some(0),
+			(Self::Next, Some(index)) => Some(index + 1),
+			(Self::Previous, None) => layers.len().checked_sub(1),
+			(Self::Previous, Some(index)) => index.checked_sub(1),
+		};
+		index.and_then(|index| layers.get(index).copied())
+	}
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ProfileError {
+	#[error("No profile named {0:?}")]
+	Missing(String),
+	#[error("A profile named {0:?} already exists")]
+	AlreadyExists(String),
+	#[error("The default profile {0:?} cannot be removed")]
+	RemoveDefault(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct DisplayProfile {
 	pub size: (u32, u32),
 	pub location: WindowPosition,
 	pub scale: f64,
+	// the submenu this profile is listed under in the system tray
+	pub group: Option<String>,
+	// when non-empty, only bindings from these layers are shown by the overlay
+	pub layers: Vec<String>,
+	// the name of the monitor this profile is automatically selected on, when the overlay is moved onto that monitor
+	pub target_monitor: Option<String>,
+	// when the window is resized, it keeps the aspect ratio of the layout's switches
+	pub aspect_lock: bool,
+}
+
+impl DisplayProfile {
+	/// The size the window should be resized to so it matches the aspect ratio of the layout's switches,
+	/// if this profile locks its aspect and `size` doesn't already match.
+	/// Whichever dimension moved furthest from the profile's size is kept, and the other is adjusted to match.
+	pub fn aspect_locked_size(&self, layout: &shared::Layout, size: (u32, u32)) -> Option<(u32, u32)> {
+		if !self.aspect_lock {
+			return None;
+		}
+		let (width, height) = shared::geometry::switch_bounds(layout, &self.size)?.size();
+		if width <= 0.0 || height <= 0.0 {
+			return None;
+		}
+		let aspect = width / height;
+		let previous = (self.size.0 as f64 * self.scale, self.size.1 as f64 * self.scale);
+		let (width, height) = (size.0 as f64, size.1 as f64);
+		let locked = match (width - previous.0).abs() >= (height - previous.1).abs() {
+			true => (width, width / aspect),
+			false => (height * aspect, height),
+		};
+		let locked = (locked.0.round() as u32, locked.1.round() as u32);
+		// sizes within a pixel are accepted, so rounding can't cause an endless series of resizes
+		if locked.0.abs_diff(size.0) <= 1 && locked.1.abs_diff(size.1) <= 1 {
+			return None;
+		}
+		Some(locked)
+	}
+}
+
+impl Default for DisplayProfile {
+	fn default() -> Self {
+		Self {
+			size: (800, 600),
+			scale: 1.0,
+			group: None,
+			layers: Vec::new(),
+			target_monitor: None,
+			aspect_lock: false,
+			location: WindowPosition {
+				anchor: WindowAnchor::Center,
+				monitor: 0,
+				offset: (0, 0),
+			},
+		}
+	}
 }
 
 impl FromKdl<()> for DisplayProfile {
@@ -185,7 +667,24 @@ impl FromKdl<()> for DisplayProfile {
 		};
 		let location = node.query_req_t("scope() > location")?;
 		let scale = node.query_f64_opt("scope() > scale", 0)?.unwrap_or(1.0);
-		Ok(Self { size, scale, location })
+		let group = node.query_str_opt("scope() > group", 0)?.map(str::to_owned);
+		let mut layers = Vec::new();
+		for mut node in node.query_all("scope() > layers")? {
+			while let Some(entry) = node.next_opt() {
+				layers.push(entry.as_str_req()?.to_owned());
+			}
+		}
+		let target_monitor = node.query_str_opt("scope() > target_monitor", 0)?.map(str::to_owned);
+		let aspect_lock = node.query_bool_opt("scope() > aspect_lock", 0)?.unwrap_or_default();
+		Ok(Self {
+			size,
+			scale,
+			location,
+			group,
+			layers,
+			target_monitor,
+			aspect_lock,
+		})
 	}
 }
 
@@ -202,6 +701,22 @@ impl AsKdl for DisplayProfile {
 			node.child(("scale", &self.scale));
 		}
 		node.child(("location", &self.location));
+		if let Some(group) = &self.group {
+			node.child(("group", group));
+		}
+		if !self.layers.is_empty() {
+			let mut layers = kdlize::NodeBuilder::default();
+			for layer in &self.layers {
+				layers.entry(layer.as_str());
+			}
+			node.child(layers.build("layers"));
+		}
+		if let Some(target_monitor) = &self.target_monitor {
+			node.child(("target_monitor", target_monitor));
+		}
+		if self.aspect_lock {
+			node.child(("aspect_lock", &self.aspect_lock));
+		}
 		node
 	}
 }
@@ -213,12 +728,67 @@ pub struct WindowPosition {
 	pub offset: (i32, i32),
 }
 
+impl WindowPosition {
+	/// The position of a window whose top-left corner is at `window_pos`, relative to the monitor containing the window's
+	/// center (or the closest monitor, if its center is off-screen). The window is anchored to the nearest edges of
+	/// that monitor, based on which third of the monitor its center is in.
+	/// `monitors` are the origin and size of each monitor in the virtual desktop, in the order they are indexed by.
+	pub fn from_window(monitors: &[((i32, i32), (u32, u32))], window_pos: (i32, i32), window_size: (u32, u32)) -> Self {
+		let center = (
+			window_pos.0 as i64 + window_size.0 as i64 / 2,
+			window_pos.1 as i64 + window_size.1 as i64 / 2,
+		);
+		// the squared distance from the window's center to each monitor, which is 0 if the monitor contains it
+		let distance = |(pos, size): &((i32, i32), (u32, u32))| {
+			let dx = center.0.clamp(pos.0 as i64, pos.0 as i64 + size.0 as i64) - center.0;
+			let dy = center.1.clamp(pos.1 as i64, pos.1 as i64 + size.1 as i64) - center.1;
+			dx * dx + dy * dy
+		};
+		let nearest = monitors.iter().enumerate().min_by_key(|(_, monitor)| distance(monitor));
+		let Some((monitor, &(monitor_pos, monitor_size))) = nearest else {
+			return Self {
+				monitor: 0,
+				anchor: WindowAnchor::TopLeft,
+				offset: (window_pos.0, -window_pos.1),
+			};
+		};
+
+		// which third of the monitor the center is in, along each axis
+		let third = |center: i64, pos: i32, size: u32| ((center - pos as i64) * 3).div_euclid(size.max(1) as i64);
+		let anchor = match (
+			third(center.0, monitor_pos.0, monitor_size.0),
+			third(center.1, monitor_pos.1, monitor_size.1),
+		) {
+			(..=0, ..=0) => WindowAnchor::TopLeft,
+			(1, ..=0) => WindowAnchor::TopCenter,
+			(_, ..=0) => WindowAnchor::TopRight,
+			(..=0, 1) => WindowAnchor::CenterLeft,
+			(1, 1) => WindowAnchor::Center,
+			(_, 1) => WindowAnchor::CenterRight,
+			(..=0, _) => WindowAnchor::BottomLeft,
+			(1, _) => WindowAnchor::BottomCenter,
+			(_, _) => WindowAnchor::BottomRight,
+		};
+		// offsets are from the anchored position, where positive y moves the window up
+		let anchored = anchor.window_position(monitor_pos, monitor_size, window_size);
+		Self {
+			monitor,
+			anchor,
+			offset: (window_pos.0 - anchored.0, anchored.1 - window_pos.1),
+		}
+	}
+}
+
 impl FromKdl<()> for WindowPosition {
 	type Error = anyhow::Error;
 
 	fn from_kdl<'doc>(node: &mut kdlize::NodeReader<'doc, ()>) -> Result<Self, Self::Error> {
-		let monitor = node.query_i64_opt("scope() > monitor", 0)?;
-		let monitor = monitor.map(|idx| (idx - 1) as usize).unwrap_or_default();
+		// monitors are numbered from 1 in the config, but indexed from 0
+		let monitor = match node.query_i64_opt("scope() > monitor", 0)? {
+			None => 0,
+			Some(number) if number >= 1 => (number - 1) as usize,
+			Some(number) => return Err(InvalidMonitor(number))?,
+		};
 		let anchor = node.query_str_req_t("scope() > anchor", 0)?;
 		let offset = {
 			let mut node = node.query_req("scope() > offset")?;
@@ -234,11 +804,15 @@ impl FromKdl<()> for WindowPosition {
 	}
 }
 
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid monitor {0}, monitors are numbered from 1")]
+pub struct InvalidMonitor(i64);
+
 impl AsKdl for WindowPosition {
 	fn as_kdl(&self) -> kdlize::NodeBuilder {
 		let mut node = kdlize::NodeBuilder::default();
 		if self.monitor != 0 {
-			node.child(("monitor", &(self.monitor + 1)));
+			node.child(("monitor", &self.monitor.saturating_add(1)));
 		}
 		node.child(("anchor", &self.anchor.to_string()));
 		node.child({
@@ -265,6 +839,31 @@ pub enum WindowAnchor {
 	Center,
 	CenterRight,
 }
+impl WindowAnchor {
+	/// Returns the top-left corner of a window of `window_size` such that the anchored edge(s) of the window
+	/// line up with the same edge(s) of the monitor (e.g. BottomCenter puts the window's bottom edge on the monitor's bottom edge).
+	/// `monitor_pos` is the monitor's origin in the virtual desktop, which is not (0, 0) for secondary monitors.
+	pub fn window_position(
+		self,
+		monitor_pos: (i32, i32),
+		monitor_size: (u32, u32),
+		window_size: (u32, u32),
+	) -> (i32, i32) {
+		let free_x = monitor_size.0 as i32 - window_size.0 as i32;
+		let free_y = monitor_size.1 as i32 - window_size.1 as i32;
+		let x = match self {
+			Self::TopLeft | Self::CenterLeft | Self::BottomLeft => 0,
+			Self::TopCenter | Self::Center | Self::BottomCenter => free_x / 2,
+			Self::TopRight | Self::CenterRight | Self::BottomRight => free_x,
+		};
+		let y = match self {
+			Self::TopLeft | Self::TopCenter | Self::TopRight => 0,
+			Self::CenterLeft | Self::Center | Self::CenterRight => free_y / 2,
+			Self::BottomLeft | Self::BottomCenter | Self::BottomRight => free_y,
+		};
+		(monitor_pos.0 + x, monitor_pos.1 + y)
+	}
+}
 impl Into<tauri_plugin_positioner::Position> for WindowAnchor {
 	fn into(self) -> tauri_plugin_positioner::Position {
 		match self {
@@ -322,7 +921,7 @@ impl std::fmt::Display for WindowAnchor {
 #[error("Invalid window anchor {0:?}")]
 pub struct InvalidWindowAnchor(String);
 
-fn key_alias_to_code(alias: shared::KeyAlias) -> Option<rdev::Key> {
+fn us_key_code(alias: shared::KeyAlias) -> Option<rdev::Key> {
 	use shared::KeyAlias as Alias;
 	match alias {
 		Alias::Backquote => Some(rdev::Key::BackQuote),
@@ -376,6 +975,7 @@ fn key_alias_to_code(alias: shared::KeyAlias) -> Option<rdev::Key> {
 		Alias::AltRight => Some(rdev::Key::AltGr),
 		Alias::Backspace => Some(rdev::Key::Backspace),
 		Alias::CapsLock => Some(rdev::Key::CapsLock),
+		Alias::ContextMenu => Some(rdev::Key::Unknown(93)),
 		Alias::ControlLeft => Some(rdev::Key::ControlLeft),
 		Alias::ControlRight => Some(rdev::Key::ControlRight),
 		Alias::Enter => Some(rdev::Key::Return),
@@ -395,6 +995,7 @@ fn key_alias_to_code(alias: shared::KeyAlias) -> Option<rdev::Key> {
 		Alias::ArrowLeft => Some(rdev::Key::LeftArrow),
 		Alias::ArrowRight => Some(rdev::Key::RightArrow),
 		Alias::ArrowUp => Some(rdev::Key::UpArrow),
+		Alias::NumLock => Some(rdev::Key::NumLock),
 		Alias::Escape => Some(rdev::Key::Escape),
 		Alias::F1 => Some(rdev::Key::F1),
 		Alias::F2 => Some(rdev::Key::F2),
@@ -454,34 +1055,185 @@ fn key_alias_to_code(alias: shared::KeyAlias) -> Option<rdev::Key> {
 	}
 }
 
-fn dealias_code(alias: shared::KeyAlias) -> Option<rdev::Key> {
+/// The key which types the alias without any modifiers, in the given keyboard locale.
+/// Key codes name the physical key in its US-ANSI position, so other locales remap the keys whose symbols differ.
+fn key_alias_to_code(alias: shared::KeyAlias, locale: KeyboardLocale) -> Option<rdev::Key> {
 	use shared::KeyAlias as Alias;
-	match alias {
-		Alias::Tilde => Some(rdev::Key::Num0),
-		Alias::Exclamation => Some(rdev::Key::Num1),
-		Alias::At => Some(rdev::Key::Num2),
-		Alias::Hash => Some(rdev::Key::Num3),
-		Alias::Dollar => Some(rdev::Key::Num4),
-		Alias::Percent => Some(rdev::Key::Num5),
-		Alias::Caret => Some(rdev::Key::Num6),
-		Alias::Ampersand => Some(rdev::Key::Num7),
-		Alias::Star => Some(rdev::Key::Num8),
-		Alias::ParenLeft => Some(rdev::Key::Num9),
-		Alias::ParenRight => Some(rdev::Key::Num0),
-		Alias::BraceLeft => Some(rdev::Key::LeftBracket),
-		Alias::BraceRight => Some(rdev::Key::RightBracket),
-		Alias::Underscore => Some(rdev::Key::Minus),
-		Alias::Plus => Some(rdev::Key::Equal),
-		Alias::Pipe => Some(rdev::Key::BackSlash),
-		Alias::Colon => Some(rdev::Key::SemiColon),
-		Alias::QuoteDouble => Some(rdev::Key::Quote),
-		Alias::LessThan => Some(rdev::Key::Comma),
-		Alias::GreaterThan => Some(rdev::Key::Dot),
-		Alias::Question => Some(rdev::Key::Slash),
-		_ => None,
+	match locale {
+		KeyboardLocale::Us => us_key_code(alias),
+		KeyboardLocale::Uk => match alias {
+			Alias::Backslash => Some(rdev::Key::IntlBackslash),
+			Alias::Hash => Some(rdev::Key::BackSlash),
+			_ => us_key_code(alias),
+		},
+		KeyboardLocale::De => match alias {
+			Alias::KeyY => Some(rdev::Key::KeyZ),
+			Alias::KeyZ => Some(rdev::Key::KeyY),
+			Alias::Caret => Some(rdev::Key::BackQuote),
+			Alias::Minus => Some(rdev::Key::Slash),
+			Alias::Plus => Some(rdev::Key::RightBracket),
+			Alias::Hash => Some(rdev::Key::BackSlash),
+			Alias::LessThan => Some(rdev::Key::IntlBackslash),
+			// typed with modifiers on a german keyboard
+			Alias::Backquote
+			| Alias::Backslash
+			| Alias::BracketLeft
+			| Alias::BracketRight
+			| Alias::Equal
+			| Alias::Quote
+			| Alias::Semicolon
+			| Alias::Slash => None,
+			_ => us_key_code(alias),
+		},
+	}
+}
+
+/// The key and modifiers which type a symbol that has no key of its own, in the given keyboard locale.
+fn dealias_code(alias: shared::KeyAlias, locale: KeyboardLocale) -> Option<HotKey> {
+	use shared::KeyAlias as Alias;
+	let shift = |code| HotKey {
+		code,
+		shift: true,
+		..Default::default()
+	};
+	let alt_gr = |code| HotKey {
+		code,
+		alt: true,
+		..Default::default()
+	};
+	match locale {
+		KeyboardLocale::Us => match alias {
+			Alias::Tilde => Some(shift(rdev::Key::BackQuote)),
+			Alias::Exclamation => Some(shift(rdev::Key::Num1)),
+			Alias::At => Some(shift(rdev::Key::Num2)),
+			Alias::Hash => Some(shift(rdev::Key::Num3)),
+			Alias::Dollar => Some(shift(rdev::Key::Num4)),
+			Alias::Percent => Some(shift(rdev::Key::Num5)),
+			Alias::Caret => Some(shift(rdev::Key::Num6)),
+			Alias::Ampersand => Some(shift(rdev::Key::Num7)),
+			Alias::Star => Some(shift(rdev::Key::Num8)),
+			Alias::ParenLeft => Some(shift(rdev::Key::Num9)),
+			Alias::ParenRight => Some(shift(rdev::Key::Num0)),
+			Alias::BraceLeft => Some(shift(rdev::Key::LeftBracket)),
+			Alias::BraceRight => Some(shift(rdev::Key::RightBracket)),
+			Alias::Underscore => Some(shift(rdev::Key::Minus)),
+			Alias::Plus => Some(shift(rdev::Key::Equal)),
+			Alias::Pipe => Some(shift(rdev::Key::BackSlash)),
+			Alias::Colon => Some(shift(rdev::Key::SemiColon)),
+			Alias::QuoteDouble => Some(shift(rdev::Key::Quote)),
+			Alias::LessThan => Some(shift(rdev::Key::Comma)),
+			Alias::GreaterThan => Some(shift(rdev::Key::Dot)),
+			Alias::Question => Some(shift(rdev::Key::Slash)),
+			_ => None,
+		},
+		KeyboardLocale::Uk => match alias {
+			Alias::Tilde => Some(shift(rdev::Key::BackSlash)),
+			Alias::At => Some(shift(rdev::Key::Quote)),
+			Alias::QuoteDouble => Some(shift(rdev::Key::Num2)),
+			Alias::Pipe => Some(shift(rdev::Key::IntlBackslash)),
+			Alias::Hash => None,
+			_ => dealias_code(alias, KeyboardLocale::Us),
+		},
+		KeyboardLocale::De => match alias {
+			Alias::Exclamation => Some(shift(rdev::Key::Num1)),
+			Alias::QuoteDouble => Some(shift(rdev::Key::Num2)),
+			Alias::Dollar => Some(shift(rdev::Key::Num4)),
+			Alias::Percent => Some(shift(rdev::Key::Num5)),
+			Alias::Ampersand => Some(shift(rdev::Key::Num6)),
+			Alias::Slash => Some(shift(rdev::Key::Num7)),
+			Alias::ParenLeft => Some(shift(rdev::Key::Num8)),
+			Alias::ParenRight => Some(shift(rdev::Key::Num9)),
+			Alias::Equal => Some(shift(rdev::Key::Num0)),
+			Alias::Question => Some(shift(rdev::Key::Minus)),
+			Alias::Backquote => Some(shift(rdev::Key::Equal)),
+			Alias::Star => Some(shift(rdev::Key::RightBracket)),
+			Alias::Quote => Some(shift(rdev::Key::BackSlash)),
+			Alias::Semicolon => Some(shift(rdev::Key::Comma)),
+			Alias::Colon => Some(shift(rdev::Key::Dot)),
+			Alias::Underscore => Some(shift(rdev::Key::Slash)),
+			Alias::GreaterThan => Some(shift(rdev::Key::IntlBackslash)),
+			Alias::At => Some(alt_gr(rdev::Key::KeyQ)),
+			Alias::BraceLeft => Some(alt_gr(rdev::Key::Num7)),
+			Alias::BracketLeft => Some(alt_gr(rdev::Key::Num8)),
+			Alias::BracketRight => Some(alt_gr(rdev::Key::Num9)),
+			Alias::BraceRight => Some(alt_gr(rdev::Key::Num0)),
+			Alias::Backslash => Some(alt_gr(rdev::Key::Minus)),
+			Alias::Tilde => Some(alt_gr(rdev::Key::RightBracket)),
+			Alias::Pipe => Some(alt_gr(rdev::Key::IntlBackslash)),
+			_ => None,
+		},
+	}
+}
+
+/// Resolves key aliases to the key codes reported when keys are pressed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Keymap {
+	pub locale: KeyboardLocale,
+	/// Raw codes for aliases, which take precedence over the locale's tables.
+	pub scancodes: BTreeMap<shared::KeyAlias, u32>,
+}
+
+impl Keymap {
+	fn code(&self, alias: shared::KeyAlias) -> Option<rdev::Key> {
+		if let Some(code) = self.scancodes.get(&alias) {
+			return Some(rdev::Key::Unknown(*code));
+		}
+		key_alias_to_code(alias, self.locale)
+	}
+
+	fn dealias(&self, alias: shared::KeyAlias) -> Option<HotKey> {
+		// an alias mapped to a raw code is always that key, never a symbol typed with modifiers
+		if self.scancodes.contains_key(&alias) {
+			return None;
+		}
+		dealias_code(alias, self.locale)
+	}
+}
+
+/// The keyboard layout the OS types with, which determines what physical keys produce each symbol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Derivative, Serialize, Deserialize)]
+#[derivative(Default)]
+pub enum KeyboardLocale {
+	/// US-ANSI
+	#[derivative(Default)]
+	Us,
+	/// UK-ISO
+	Uk,
+	/// German QWERTZ
+	De,
+}
+
+impl std::str::FromStr for KeyboardLocale {
+	type Err = InvalidKeyboardLocale;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"us" => Ok(Self::Us),
+			"uk" => Ok(Self::Uk),
+			"de" => Ok(Self::De),
+			_ => Err(InvalidKeyboardLocale(s.to_owned())),
+		}
+	}
+}
+
+impl std::fmt::Display for KeyboardLocale {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Self::Us => "us",
+				Self::Uk => "uk",
+				Self::De => "de",
+			}
+		)
 	}
 }
 
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid keyboard locale {0:?}, expecting us, uk, or de")]
+pub struct InvalidKeyboardLocale(String);
+
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub struct HotKey {
 	pub code: rdev::Key,
@@ -524,6 +1276,20 @@ impl HotKey {
 		keys
 	}
 
+	fn is_modifier(code: rdev::Key) -> bool {
+		matches!(
+			code,
+			rdev::Key::ShiftLeft
+				| rdev::Key::ShiftRight
+				| rdev::Key::ControlLeft
+				| rdev::Key::ControlRight
+				| rdev::Key::Alt
+				| rdev::Key::AltGr
+				| rdev::Key::MetaLeft
+				| rdev::Key::MetaRight
+		)
+	}
+
 	fn insert(&mut self, code: rdev::Key) {
 		match code {
 			rdev::Key::ShiftLeft | rdev::Key::ShiftRight => self.shift = true,
@@ -599,12 +1365,21 @@ impl std::fmt::Display for HotKey {
 	}
 }
 
-pub fn alias_hotkeys(combo: &shared::KeySet) -> Vec<HotKey> {
+/// Returns the keys of a chord (a key set with multiple non-modifier keys), which are triggered while all of the keys are held.
+/// Key sets with only one non-modifier key are not chords, and are matched via `alias_hotkeys`.
+pub fn chord_keys(combo: &shared::KeySet, keymap: &Keymap) -> Option<HashSet<rdev::Key>> {
+	let codes = combo.iter().filter_map(|alias| keymap.code(*alias));
+	let codes = codes.collect::<HashSet<_>>();
+	let non_modifiers = codes.iter().filter(|code| !HotKey::is_modifier(**code)).count();
+	(non_modifiers > 1).then_some(codes)
+}
+
+pub fn alias_hotkeys(combo: &shared::KeySet, keymap: &Keymap) -> Vec<HotKey> {
 	let mut hotkeys = Vec::with_capacity(3);
 
 	if let Some(alias) = combo.get_single() {
 		// Simple conversions, alias directly matches some code
-		if let Some(code) = key_alias_to_code(alias) {
+		if let Some(code) = keymap.code(alias) {
 			hotkeys.push(HotKey {
 				code,
 				..Default::default()
@@ -620,23 +1395,681 @@ pub fn alias_hotkeys(combo: &shared::KeySet) -> Vec<HotKey> {
 		}
 
 		// Symbols which are represented by other codes
-		if let Some(code) = dealias_code(alias) {
-			hotkeys.push(HotKey {
-				code,
-				shift: true,
-				..Default::default()
-			});
+		if let Some(hotkey) = keymap.dealias(alias) {
+			hotkeys.push(hotkey);
 		}
 	} else {
 		let mut hotkey = HotKey::default();
 		for alias in combo.iter() {
-			let Some(code) = key_alias_to_code(*alias) else {
+			if let Some(code) = keymap.code(*alias) {
+				hotkey.insert(code);
+				continue;
+			}
+			// Symbols without a key of their own (e.g. "LControl++") also need the modifiers which type them
+			let Some(symbol) = keymap.dealias(*alias) else {
 				continue;
 			};
-			hotkey.insert(code);
+			hotkey.code = symbol.code;
+			hotkey.shift |= symbol.shift;
+			hotkey.ctrl |= symbol.ctrl;
+			hotkey.alt |= symbol.alt;
+			hotkey.meta |= symbol.meta;
 		}
 		hotkeys.push(hotkey);
 	}
 
 	hotkeys
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	static SAMPLE_LAYOUT: &str = r#"
+default_profile "default"
+layout {
+	default_layer "base"
+	tap_window 300
+	switch "l_index" 120.5 -20 side="left"
+	switch "r_index" 120.5 -20 side="right"
+	switch "thumb" 0 -150
+	combo "esc" 0 25.5 (IconBootstrap)"escape" {
+		layers "base" "nav"
+		link {
+			switch "l_index" 0 -1
+			point (+)10 (-)20 (X)15
+			anchor 0 1
+		}
+		bind "Escape" layer="nav"
+	}
+	layer "base" {
+		bind "l_index" {
+			slot "Tap" "F"
+			slot "Hold" "LShift" "Shift"
+		}
+		bind "r_index" {
+			slot "Tap" "J"
+		}
+		bind "thumb" {
+			slot "Tap" "Space" (IconCustom)"space"
+			slot "Hold" "F13" layer="nav"
+		}
+	}
+	layer "nav" {
+		bind "l_index" {
+			slot "Tap" "ArrowLeft" (IconBootstrap)"arrow-left"
+		}
+	}
+}
+"#;
+
+	/// Parses a config, asserting that it is unchanged by saving and reloading it.
+	fn parse_roundtrip(kdl: &str) -> Config {
+		let config = parse_config_kdl(kdl).expect("failed to parse config");
+		let serialized = serialize_config_kdl(&config);
+		assert_eq!(parse_config_kdl(&serialized).ok(), Some(config.clone()), "{serialized}");
+		config
+	}
+
+	fn sample_config() -> Config {
+		let layout = parse_config_kdl(SAMPLE_LAYOUT)
+			.expect("sample layout should parse")
+			.layout;
+		Config {
+			default_profile: "streaming".into(),
+			active_profile: "recording".into(),
+			profiles: [
+				(
+					"streaming".into(),
+					DisplayProfile {
+						size: (800, 400),
+						scale: 1.0,
+						group: None,
+						layers: Vec::new(),
+						target_monitor: None,
+						aspect_lock: false,
+						location: WindowPosition {
+							monitor: 0,
+							anchor: WindowAnchor::BottomCenter,
+							offset: (0, 20),
+						},
+					},
+				),
+				(
+					"recording".into(),
+					DisplayProfile {
+						size: (1024, 512),
+						scale: 1.5,
+						group: Some("Work".into()),
+						layers: vec!["base".into()],
+						target_monitor: Some("DISPLAY2".into()),
+						aspect_lock: true,
+						location: WindowPosition {
+							monitor: 2,
+							anchor: WindowAnchor::TopRight,
+							offset: (-10, 35),
+						},
+					},
+				),
+			]
+			.into(),
+			layout,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn sample_layout_is_populated() {
+		let config = sample_config();
+		assert_eq!(config.layout().default_layer(), "base");
+		assert_eq!(config.layout().switches().len(), 3);
+		assert_eq!(config.layout().combos().len(), 1);
+		assert_eq!(
+			config.layout().layer_order(),
+			&vec!["base".to_owned(), "nav".to_owned()]
+		);
+	}
+
+	#[test]
+	fn json_roundtrip() {
+		let config = sample_config();
+		let json = serde_json::to_string(&config).expect("failed to serialize json");
+		let parsed = serde_json::from_str::<Config>(&json).expect("failed to deserialize json");
+		assert_eq!(parsed, config);
+	}
+
+	#[test]
+	fn kdl_roundtrip() {
+		let config = sample_config();
+		let kdl = serialize_config_kdl(&config);
+		let parsed = parse_config_kdl(&kdl).expect("failed to parse serialized kdl");
+		assert_eq!(parsed, config);
+	}
+
+	#[test]
+	fn kdl_roundtrip_default() {
+		let config = Config::default();
+		let kdl = serialize_config_kdl(&config);
+		let parsed = parse_config_kdl(&kdl).expect("failed to parse serialized kdl");
+		assert_eq!(parsed, config);
+	}
+
+	#[test]
+	fn kdl_cleared_active_profile_falls_back_to_default() {
+		// `active_profile` is omitted from kdl when empty (i.e. exported via `clear_state`),
+		// so parsing it back must select the default profile rather than an empty name.
+		let mut config = sample_config();
+		config.clear_state();
+		let kdl = serialize_config_kdl(&config);
+		assert!(!kdl.contains("active_profile"));
+		let parsed = parse_config_kdl(&kdl).expect("failed to parse serialized kdl");
+		assert_eq!(parsed.active_profile, parsed.default_profile);
+		assert!(parsed.active_profile().is_some());
+	}
+
+	#[test]
+	fn validate() {
+		assert_eq!(sample_config().validate(), Ok(()));
+		assert_eq!(Config::default().validate(), Ok(()));
+
+		let mut config = sample_config();
+		config.default_profile = "missing".into();
+		assert_eq!(
+			config.validate(),
+			Err(ValidationError::MissingDefaultProfile("missing".into()))
+		);
+
+		let invalid_layout = SAMPLE_LAYOUT.replace(r#"bind "r_index""#, r#"bind "r_pinky""#);
+		let mut config = sample_config();
+		config.layout = parse_config_kdl(&invalid_layout).expect("layout should parse").layout;
+		assert_eq!(
+			config.validate(),
+			Err(ValidationError::UnknownSwitch {
+				layer: "base".into(),
+				switch: "r_pinky".into(),
+			})
+		);
+	}
+
+	#[test]
+	fn validate_layer_order() {
+		// layouts sent over ipc are deserialized with serde, which doesn't rebuild the layer order
+		let mut layout = serde_json::to_value(sample_config().layout).expect("failed to serialize layout");
+		layout["layer_order"] = serde_json::json!(["base", "navv"]);
+		let mut config = sample_config();
+		config.layout = serde_json::from_value(layout).expect("failed to deserialize layout");
+		assert_eq!(
+			config.validate(),
+			Err(ValidationError::LayerOrder(shared::LayerOrderMismatch {
+				order: vec!["base".into(), "navv".into()],
+				layers: vec!["base".into(), "nav".into()],
+			}))
+		);
+	}
+
+	#[test]
+	fn lint_layout() {
+		assert_eq!(sample_config().lint_layout(), Vec::new());
+
+		let invalid_layout = SAMPLE_LAYOUT.replace(r#"switch "l_index" 0 -1"#, r#"switch "l_indx" 0 -1"#);
+		let mut config = sample_config();
+		config.layout = parse_config_kdl(&invalid_layout).expect("layout should parse").layout;
+		let unknown_switch = ValidationError::ComboUnknownSwitch {
+			combo: "esc".into(),
+			switch: "l_indx".into(),
+		};
+		assert_eq!(config.lint_layout(), vec![unknown_switch.clone()]);
+		assert_eq!(config.validate(), Err(unknown_switch));
+	}
+
+	#[test]
+	fn window_monitor_numbers_from_one() {
+		let kdl = serialize_config_kdl(&sample_config());
+		assert!(kdl.contains("monitor 3"));
+		for invalid in ["0", "-1", "-9223372036854775808"] {
+			let invalid_kdl = kdl.replace("monitor 3", &format!("monitor {invalid}"));
+			let err = parse_config_kdl(&invalid_kdl).expect_err("monitor should be invalid");
+			assert!(format!("{err:#}").contains("monitors are numbered from 1"), "{err:#}");
+		}
+	}
+
+	#[test]
+	fn malformed_kdl_never_panics() {
+		// A deterministic sweep of common mistakes, alongside the `parse_layout` fuzz target in `crates/shared/fuzz`:
+		// every mutation of a valid config must either parse or return an error, so a broken config file can never
+		// crash the app on load.
+		let kdl = serialize_config_kdl(&sample_config());
+		let lines = kdl.lines().collect::<Vec<_>>();
+		let mut mutations = Vec::new();
+		for (idx, line) in lines.iter().enumerate() {
+			// missing nodes, missing children, and unbalanced braces
+			let mut dropped = lines.clone();
+			dropped.remove(idx);
+			mutations.push(dropped.join("\n"));
+			// values of the wrong type, where numbers become strings and strings become numbers,
+			// and numbers at the boundaries of what they can hold
+			let boundaries = ["\"text\"", "0", "-1", "-9223372036854775808", "9223372036854775807"];
+			for replacement in boundaries {
+				let swapped = line.split(' ').map(|token| {
+					let is_str = token.len() > 1 && token.starts_with('"') && token.ends_with('"');
+					match (token.parse::<f64>().is_ok(), is_str) {
+						(true, _) => replacement,
+						(false, true) => "0",
+						(false, false) => token,
+					}
+				});
+				let mut swapped_lines = lines.clone();
+				let swapped = swapped.collect::<Vec<_>>().join(" ");
+				swapped_lines[idx] = &swapped;
+				mutations.push(swapped_lines.join("\n"));
+			}
+			// typed values (like link point directions and binding icons) with unknown or missing types
+			let mut untyped_lines = lines.clone();
+			let untyped = line
+				.replace("(+)", "(*)")
+				.replace("(-)", "")
+				.replace("(IconBootstrap)", "(Icon)");
+			untyped_lines[idx] = &untyped;
+			mutations.push(untyped_lines.join("\n"));
+		}
+		// documents cut off at any point
+		for (idx, _) in kdl.char_indices() {
+			mutations.push(kdl[..idx].to_owned());
+		}
+		// configs which do parse must also save, since they are written back when the profile changes
+		for mutation in mutations {
+			if let Ok(config) = parse_config_kdl(&mutation) {
+				let _ = serialize_config_kdl(&config);
+			}
+		}
+	}
+
+	#[test]
+	fn reset_profile() {
+		let mut config = sample_config();
+		assert_eq!(config.reset_profile("recording"), Ok(()));
+		let defaults = DisplayProfile::default();
+		let recording = config.profile("recording").expect("missing profile");
+		assert_eq!(recording.size, defaults.size);
+		assert_eq!(recording.location, defaults.location);
+		assert_eq!(recording.scale, defaults.scale);
+		assert_eq!(recording.group, Some("Work".into()));
+		// other profiles are untouched
+		assert_eq!(config.profile("streaming"), sample_config().profile("streaming"));
+		assert_eq!(
+			config.reset_profile("missing"),
+			Err(ProfileError::Missing("missing".into()))
+		);
+	}
+
+	#[test]
+	fn aspect_locked_size() {
+		let config = sample_config();
+		let layout = config.layout();
+		// the sample switches span 292x181 pixels, and the recording profile is 1536x768 once scaled
+		let recording = config.profile("recording").expect("missing profile");
+		assert_eq!(recording.aspect_locked_size(layout, (1800, 768)), Some((1800, 1116)));
+		assert_eq!(recording.aspect_locked_size(layout, (1536, 900)), Some((1452, 900)));
+		assert_eq!(recording.aspect_locked_size(layout, (1800, 1116)), None);
+		let streaming = config.profile("streaming").expect("missing profile");
+		assert_eq!(streaming.aspect_locked_size(layout, (1800, 768)), None);
+	}
+
+	#[test]
+	fn add_profile() {
+		let mut config = sample_config();
+		assert_eq!(config.add_profile("tutorial", DisplayProfile::default()), Ok(()));
+		assert_eq!(config.profile("tutorial"), Some(&DisplayProfile::default()));
+		assert_eq!(
+			config.add_profile("streaming", DisplayProfile::default()),
+			Err(ProfileError::AlreadyExists("streaming".into()))
+		);
+		// the existing profile is untouched
+		assert_eq!(
+			config.profile("streaming").map(|profile| profile.size),
+			Some((800, 400))
+		);
+	}
+
+	#[test]
+	fn remove_profile() {
+		let mut config = sample_config();
+		assert_eq!(
+			config.remove_profile("missing"),
+			Err(ProfileError::Missing("missing".into()))
+		);
+		assert_eq!(
+			config.remove_profile("streaming"),
+			Err(ProfileError::RemoveDefault("streaming".into()))
+		);
+		assert!(config.profile("streaming").is_some());
+
+		// removing the active profile falls back to the default
+		let removed = config.remove_profile("recording").expect("failed to remove profile");
+		assert_eq!(removed.scale, 1.5);
+		assert_eq!(config.active_profile, "streaming");
+		assert!(config.profile("recording").is_none());
+	}
+
+	#[test]
+	fn rename_profile() {
+		let mut config = sample_config();
+		assert_eq!(
+			config.rename_profile("missing", "other"),
+			Err(ProfileError::Missing("missing".into()))
+		);
+		assert_eq!(
+			config.rename_profile("streaming", "recording"),
+			Err(ProfileError::AlreadyExists("recording".into()))
+		);
+		assert_eq!(config.rename_profile("streaming", "streaming"), Ok(()));
+
+		assert_eq!(config.rename_profile("streaming", "live"), Ok(()));
+		assert_eq!(config.default_profile_id(), "live");
+		assert_eq!(config.active_profile, "recording");
+		assert!(config.profile("streaming").is_none());
+		assert!(config.profile("live").is_some());
+
+		assert_eq!(config.rename_profile("recording", "capture"), Ok(()));
+		assert_eq!(config.active_profile, "capture");
+		assert_eq!(config.active_profile().map(|profile| profile.scale), Some(1.5));
+	}
+
+	#[test]
+	fn window_anchor_position() {
+		// a secondary monitor to the left of and below the primary
+		let monitor_pos = (-1920, 200);
+		let monitor_size = (1920, 1080);
+		let window_size = (800, 600);
+		let position = |anchor: WindowAnchor| anchor.window_position(monitor_pos, monitor_size, window_size);
+		assert_eq!(position(WindowAnchor::TopLeft), (-1920, 200));
+		assert_eq!(position(WindowAnchor::Center), (-1920 + 560, 200 + 240));
+		assert_eq!(position(WindowAnchor::BottomCenter), (-1920 + 560, 200 + 480));
+		assert_eq!(position(WindowAnchor::CenterRight), (-1920 + 1120, 200 + 240));
+	}
+
+	#[test]
+	fn window_position_from_window() {
+		let monitors = [((0, 0), (1920, 1080)), ((-1920, 200), (1920, 1080))];
+		let window_size = (800, 600);
+		// a window placed by a position is captured as that same position
+		let location = WindowPosition {
+			monitor: 1,
+			anchor: WindowAnchor::BottomCenter,
+			offset: (10, 20),
+		};
+		let (monitor_pos, monitor_size) = monitors[1];
+		let window_pos = location.anchor.window_position(monitor_pos, monitor_size, window_size);
+		let window_pos = (window_pos.0 + location.offset.0, window_pos.1 - location.offset.1);
+		assert_eq!(
+			WindowPosition::from_window(&monitors, window_pos, window_size),
+			location
+		);
+		// a window near the top right of the primary monitor is anchored to that corner, even when it overlaps the edge
+		let location = WindowPosition::from_window(&monitors, (1500, 20), window_size);
+		assert_eq!(location.monitor, 0);
+		assert_eq!(location.anchor, WindowAnchor::TopRight);
+		assert_eq!(location.offset, (380, -20));
+	}
+
+	#[test]
+	fn locale_dealias() {
+		let at = "@".parse::<shared::KeySet>().expect("failed to parse key set");
+		let hotkeys = |locale| {
+			let keymap = Keymap {
+				locale,
+				..Default::default()
+			};
+			alias_hotkeys(&at, &keymap)
+		};
+		let shift = |code| HotKey {
+			code,
+			shift: true,
+			..Default::default()
+		};
+		assert_eq!(hotkeys(KeyboardLocale::Us), vec![shift(rdev::Key::Num2)]);
+		assert_eq!(hotkeys(KeyboardLocale::Uk), vec![shift(rdev::Key::Quote)]);
+		assert_eq!(
+			hotkeys(KeyboardLocale::De),
+			vec![HotKey {
+				code: rdev::Key::KeyQ,
+				alt: true,
+				..Default::default()
+			}]
+		);
+	}
+
+	#[test]
+	fn symbol_hotkeys() {
+		static SYMBOLS: &str = "` \\ [ ] , = - . ' ; / ~ ! @ # $ % ^ & * ( ) { } _ + | : \" < > ?";
+		for locale in [KeyboardLocale::Us, KeyboardLocale::Uk, KeyboardLocale::De] {
+			let keymap = Keymap {
+				locale,
+				..Default::default()
+			};
+			for symbol in SYMBOLS.split(' ') {
+				let keys = symbol.parse::<shared::KeySet>().expect("failed to parse key set");
+				let hotkeys = alias_hotkeys(&keys, &keymap);
+				let is_empty = |hotkey: &HotKey| hotkey.code == HotKey::default().code;
+				assert!(
+					!hotkeys.is_empty() && !hotkeys.iter().any(is_empty),
+					"{symbol:?} has no key in {locale:?}: {hotkeys:?}"
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn modified_symbol_hotkey() {
+		let keys = "LControl++".parse::<shared::KeySet>().expect("failed to parse key set");
+		assert_eq!(keys.to_string(), "LControl++");
+		let hotkey = HotKey {
+			code: rdev::Key::Equal,
+			shift: true,
+			ctrl: true,
+			..Default::default()
+		};
+		assert_eq!(alias_hotkeys(&keys, &Keymap::default()), vec![hotkey]);
+	}
+
+	#[test]
+	fn scancode_alias() {
+		let kdl = r#"
+default_profile "default"
+scancode_alias {
+	code 191 "F13"
+	code 201 "F22"
+}
+layout {
+	default_layer "base"
+}
+"#;
+		let config = parse_roundtrip(kdl);
+		// each alias is triggered by its own scancode, such as each direction of an encoder
+		for (alias, code) in [("F13", 191), ("F22", 201)] {
+			let keys = alias.parse::<shared::KeySet>().expect("failed to parse key set");
+			let hotkey = HotKey {
+				code: rdev::Key::Unknown(code),
+				..Default::default()
+			};
+			assert_eq!(alias_hotkeys(&keys, &config.keymap()), vec![hotkey]);
+		}
+	}
+
+	#[test]
+	fn config_changes() {
+		let previous = sample_config();
+		assert_eq!(previous.changes_since(&previous), Vec::<String>::new());
+		let kdl = r#"
+default_profile "default"
+profile "default" {
+	size 800 400
+	location {
+		anchor "Center"
+		offset 0 0
+	}
+}
+layout {
+	default_layer "base"
+	switch "l_index" 120.5 -20 side="left"
+	switch "r_index" 120.5 -20 side="right"
+	layer "base" {
+		bind "l_index" {
+			slot "Tap" "F"
+		}
+	}
+}
+"#;
+		let config = parse_config_kdl(kdl).expect("failed to parse config");
+		assert_eq!(
+			config.changes_since(&previous),
+			vec![
+				"added profile \"default\"",
+				"removed profile \"recording\"",
+				"removed profile \"streaming\"",
+				"default profile is now \"default\"",
+				"changed layer \"base\" (3 -> 1 bindings)",
+				"removed layer \"nav\"",
+				"changed switches (3 -> 2)",
+				"changed combos (1 -> 0)",
+			]
+		);
+	}
+
+	#[test]
+	fn profile_for_monitor() {
+		let config = sample_config();
+		assert_eq!(config.profile_for_monitor("DISPLAY2"), Some(&"recording".to_owned()));
+		assert_eq!(config.profile_for_monitor("DISPLAY1"), None);
+	}
+
+	#[test]
+	fn shared_bindings() {
+		let kdl = r#"
+default_profile "default"
+layout {
+	default_layer "base"
+	switch "l_index" 0 0
+	switch "r_index" 50 0
+	layer "base" {
+		bind "r_index" {
+			slot "Tap" "J"
+		}
+	}
+	layer "nav" {
+		bind "l_index" {
+			also "sym" "num"
+			slot "Tap" "Escape"
+		}
+	}
+	layer "sym" {
+	}
+	layer "num" {
+		bind "l_index" {
+			slot "Tap" "0"
+		}
+	}
+}
+"#;
+		let config = parse_roundtrip(kdl);
+		assert_eq!(config.validate(), Ok(()));
+
+		let config = parse_config_kdl(&kdl.replace(r#""sym" "num""#, r#""sym" "fn""#)).expect("failed to parse config");
+		assert_eq!(
+			config.validate(),
+			Err(ValidationError::UnknownSharedLayer {
+				layer: "nav".into(),
+				switch: "l_index".into(),
+				target: "fn".into(),
+			})
+		);
+	}
+
+	#[test]
+	fn registered_hotkeys() {
+		let kdl = r#"
+default_profile "default"
+layout {
+	default_layer "base"
+	switch "l_index" 0 0
+	switch "r_index" 50 0
+	combo "esc" 25 25 "Esc" {
+		bind "F+J"
+	}
+	layer "base" {
+		bind "l_index" {
+			slot "Tap" "F"
+		}
+		bind "r_index" {
+			slot "Tap" "LControl+C"
+		}
+	}
+	layer "nav" {
+		bind "l_index" {
+			slot "Tap" "F"
+		}
+	}
+}
+"#;
+		let config = parse_config_kdl(kdl).expect("failed to parse config");
+		let hotkeys = config.registered_hotkeys();
+		let hotkey = |code, shift, ctrl| HotKey {
+			code,
+			shift,
+			ctrl,
+			..Default::default()
+		};
+		let l_index = ("l_index".to_owned(), hotkey(rdev::Key::KeyF, false, false));
+		assert_eq!(hotkeys.iter().filter(|registered| **registered == l_index).count(), 1);
+		let upper_f = ("l_index".to_owned(), hotkey(rdev::Key::KeyF, true, false));
+		assert!(hotkeys.contains(&upper_f));
+		let copy = ("r_index".to_owned(), hotkey(rdev::Key::KeyC, false, true));
+		assert!(hotkeys.contains(&copy));
+		// the combo is a chord, which isn't matched as a hotkey
+		assert!(hotkeys.iter().all(|(switch_id, _)| switch_id != "esc"));
+	}
+
+	#[test]
+	fn layer_step() {
+		let layer_order = ["base", "nav", "sym"].map(str::to_owned);
+		let (base, nav, sym) = (&layer_order[0], &layer_order[1], &layer_order[2]);
+		assert_eq!(LayerStep::Next.step(&layer_order, base, None), Some(nav));
+		assert_eq!(LayerStep::Next.step(&layer_order, base, Some(nav)), Some(sym));
+		assert_eq!(LayerStep::Next.step(&layer_order, base, Some(sym)), None);
+		assert_eq!(LayerStep::Previous.step(&layer_order, base, None), Some(sym));
+		assert_eq!(LayerStep::Previous.step(&layer_order, base, Some(nav)), None);
+
+		let kdl = r#"
+default_profile "default"
+layer_step_next "LControl+F12"
+layer_step_previous "LControl+F11"
+layout {
+	default_layer "base"
+}
+"#;
+		let config = parse_roundtrip(kdl);
+		assert_eq!(config.layer_step_hotkeys().count(), 2);
+	}
+
+	#[test]
+	fn default_config_is_valid() {
+		let config = default_config().expect("default config should parse");
+		assert_eq!(config.validate(), Ok(()));
+		assert!(config.active_profile().is_some());
+		assert_eq!(config.layout().switches().len(), 31);
+	}
+
+	#[test]
+	fn window_label() {
+		assert_eq!(Config::default().window_label(), DEFAULT_WINDOW_LABEL);
+		let kdl = r#"
+default_profile "default"
+window_label "overlay"
+layout {
+	default_layer "base"
+}
+"#;
+		let config = parse_roundtrip(kdl);
+		assert_eq!(config.window_label(), "overlay");
+	}
+}
@@ -0,0 +1,41 @@
+use super::is_saved_config;
+use std::{path::PathBuf, time::Duration};
+
+static POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Watches the config file for edits made outside of the app (e.g. in a text editor) by polling its modified time.
+/// Bursts of writes are coalesced by waiting until the file has stopped changing for a full poll interval,
+/// and writes made by the app itself (via `save_config`) are ignored, so saving never feeds back into a reload.
+pub fn spawn_config_watcher<F>(path: PathBuf, on_changed: F)
+where
+	F: Fn(String) + Send + 'static,
+{
+	std::thread::spawn(move || {
+		let modified_at = |path: &PathBuf| std::fs::metadata(path).and_then(|meta| meta.modified()).ok();
+		let mut last_modified = modified_at(&path);
+		let mut pending = false;
+		loop {
+			std::thread::sleep(POLL_INTERVAL);
+			let modified = modified_at(&path);
+			if modified != last_modified {
+				// the file may still be being written, so wait until it settles
+				last_modified = modified;
+				pending = true;
+				continue;
+			}
+			if !pending {
+				continue;
+			}
+			pending = false;
+
+			let Ok(contents) = std::fs::read_to_string(&path) else {
+				continue;
+			};
+			if is_saved_config(&contents) {
+				continue;
+			}
+			log::info!(target: "config", "Config file changed on disk, reloading");
+			on_changed(contents);
+		}
+	});
+}
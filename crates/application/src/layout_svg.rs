@@ -0,0 +1,141 @@
+use shared::{
+	geometry::{build_link_path, calc_combo_pos, calc_switch_pos, SWITCH_BORDER_WIDTH},
+	BindingDisplay, Layout, Link, SwitchSlot,
+};
+use std::fmt::Write;
+
+/// Renders a static svg image of the layout, labeled with the bindings of the default layer.
+/// Uses the same geometry as the frontend, so the image matches what the overlay displays.
+pub fn render_layout_svg(layout: &Layout, window_size: (u32, u32)) -> String {
+	let (width, height) = window_size;
+	let border = SWITCH_BORDER_WIDTH as f64;
+	let mut svg = String::new();
+	let _ = write!(
+		svg,
+		r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+	);
+	let _ = write!(
+		svg,
+		r#"<rect width="{width}" height="{height}" fill="rgb(45, 45, 45)" fill-opacity="0.6" />"#
+	);
+
+	let default_layer = layout.get_layer(layout.default_layer());
+	for (switch_id, switch) in layout.switches() {
+		let bindings = default_layer.and_then(|layer| layer.get_binding(switch_id));
+		// the default layer can move or resize switches it binds
+		let switch = bindings.map_or_else(|| switch.clone(), |bindings| bindings.override_switch(switch));
+		let switch = &layout.switch_placement(switch_id, &switch);
+		let pos = calc_switch_pos(&window_size, layout.origin(), layout.split(), switch);
+		let size = switch.size() as f64;
+		let radius = layout.switch_shape(switch).border_radius((size + border) as f32) as f64;
+		let rotation = layout.split().rotation(switch.side);
+		write_box(&mut svg, switch_id, pos, size, border, radius, rotation);
+
+		let Some(bindings) = bindings else {
+			continue;
+		};
+		let center = (pos.0 + border + size * 0.5, pos.1 + border + size * 0.5);
+		for (slot, binding) in &bindings.slots {
+			let (y, font_size) = match slot {
+				SwitchSlot::Tap => (center.1, 20),
+				SwitchSlot::Hold => (center.1 + size * 0.35, 10),
+			};
+			// labels stay level, but move with the switch when it is rotated
+			let offset = layout.split().rotate(switch.side, (0.0, y - center.1));
+			write_label(
+				&mut svg,
+				&display_label(&binding.display_or_default()),
+				(center.0 + offset.0, center.1 + offset.1),
+				font_size,
+			);
+		}
+	}
+
+	for combo in layout.combos() {
+		let arrow_id = format!("{}-arrow", escape_xml(&combo.id));
+		if combo.links.iter().any(Link::arrow) {
+			let _ = write!(
+				svg,
+				r#"<defs><marker id="{arrow_id}" viewBox="0 0 10 10" refX="8" refY="5" markerWidth="6" markerHeight="6" orient="auto-start-reverse"><path d="M 0 0 L 10 5 L 0 10 z" fill="white" /></marker></defs>"#
+			);
+		}
+		for link in &combo.links {
+			match build_link_path(layout, &window_size, combo, link) {
+				Ok(path) => {
+					let _ = write!(svg, r#"<path d="{path}" stroke="white" stroke-width="2" fill="none""#);
+					if link.arrow() {
+						let _ = write!(svg, r#" marker-end="url(#{arrow_id})""#);
+					}
+					if let Some(dash) = link.dash() {
+						let _ = write!(svg, r#" stroke-dasharray="{}""#, escape_xml(dash));
+					}
+					svg += " />";
+				}
+				Err(err) => {
+					log::error!(target: "layout_svg", "{err}");
+				}
+			}
+		}
+		let pos = calc_combo_pos(&window_size, layout.origin(), layout.split(), combo);
+		let rotation = layout.split().rotation(combo.side);
+		let size = combo.size();
+		let radius = combo.shape().border_radius((size + border) as f32) as f64;
+		write_box(&mut svg, &combo.id, pos, size, border, radius, rotation);
+		let center = (pos.0 + border + size * 0.5, pos.1 + border + size * 0.5);
+		write_label(&mut svg, &display_label(&combo.label), center, 12);
+	}
+
+	svg += "</svg>";
+	svg
+}
+
+/// Writes a bordered box whose outer top-left corner is at `pos`, matching the css box model of switches.
+/// The box is rotated clockwise by `rotation` degrees about its center.
+fn write_box(svg: &mut String, id: &str, pos: (f64, f64), size: f64, border: f64, radius: f64, rotation: f64) {
+	let center = (pos.0 + border + size * 0.5, pos.1 + border + size * 0.5);
+	let _ = write!(
+		svg,
+		r#"<rect id="{}" x="{}" y="{}" width="{}" height="{}" rx="{radius}" fill="none" stroke="white" stroke-width="{border}" transform="rotate({rotation} {} {})" />"#,
+		escape_xml(id),
+		pos.0 + border * 0.5,
+		pos.1 + border * 0.5,
+		size + border,
+		size + border,
+		center.0,
+		center.1,
+	);
+}
+
+fn write_label(svg: &mut String, text: &str, pos: (f64, f64), font_size: u32) {
+	let _ = write!(
+		svg,
+		r#"<text x="{}" y="{}" font-family="Ubuntu Mono, monospace" font-weight="700" font-size="{font_size}" fill="white" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+		pos.0,
+		pos.1,
+		escape_xml(text),
+	);
+}
+
+fn display_label(display: &BindingDisplay) -> String {
+	match display {
+		BindingDisplay::Text(value) => value.clone(),
+		// icons are rendered by the frontend's icon fonts/glyphs, so the best we can do is name them
+		BindingDisplay::IconBootstrap(value) => value.clone(),
+		BindingDisplay::IconCustom(value) => value.clone(),
+	}
+}
+
+fn escape_xml(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'&' => escaped += "&amp;",
+			'<' => escaped += "&lt;",
+			'>' => escaped += "&gt;",
+			'"' => escaped += "&quot;",
+			'\'' => escaped += "&apos;",
+			c => escaped.push(c),
+		}
+	}
+	escaped
+}
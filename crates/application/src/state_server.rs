@@ -0,0 +1,86 @@
+use serde::Serialize;
+use std::{
+	io::{BufRead, BufReader, Write},
+	net::{TcpListener, TcpStream},
+	time::Duration,
+};
+
+/// How long a connection can take to send its request before it is dropped,
+/// so an idle (or speculatively preconnected) socket can't block the server.
+static REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Origins which are allowed to read the state cross-origin.
+/// Browser sources loaded from a local file send the `null` origin, while any other web page open in the user's
+/// browser could otherwise poll which keys are pressed.
+static ALLOWED_ORIGINS: &[&str] = &["null"];
+
+/// The active input state, as reported to external overlays (e.g. OBS browser sources).
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ActiveState {
+	pub active_layers: Vec<String>,
+	pub active_switches: Vec<String>,
+}
+
+/// Serves `GET /state` on localhost at the provided port, responding with the json of the current `ActiveState`.
+/// Requests are handled one at a time on a dedicated thread, which is plenty for a widget polling the state.
+pub fn spawn_state_server<F>(port: u16, get_state: F)
+where
+	F: Fn() -> ActiveState + Send + 'static,
+{
+	std::thread::spawn(move || {
+		let listener = match TcpListener::bind(("127.0.0.1", port)) {
+			Ok(listener) => listener,
+			Err(err) => {
+				log::error!(target: "state_server", "Failed to listen on port {port}: {err:?}");
+				return;
+			}
+		};
+		log::info!(target: "state_server", "Serving active state at http://127.0.0.1:{port}/state");
+		for stream in listener.incoming() {
+			let result = stream
+				.map_err(anyhow::Error::from)
+				.and_then(|stream| handle(stream, &get_state));
+			if let Err(err) = result {
+				log::warn!(target: "state_server", "Failed to handle request: {err:?}");
+			}
+		}
+	});
+}
+
+fn handle(mut stream: TcpStream, get_state: &impl Fn() -> ActiveState) -> anyhow::Result<()> {
+	stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+	let mut reader = BufReader::new(&mut stream);
+	let mut request_line = String::new();
+	reader.read_line(&mut request_line)?;
+	// the rest of the headers are read before responding, so closing the connection doesn't reset it
+	let mut origin = None;
+	loop {
+		let mut header = String::new();
+		if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+			break;
+		}
+		if let Some((name, value)) = header.split_once(':') {
+			if name.trim().eq_ignore_ascii_case("origin") {
+				origin = Some(value.trim().to_owned());
+			}
+		}
+	}
+
+	let mut parts = request_line.split_whitespace();
+	let (status, body) = match (parts.next(), parts.next()) {
+		(Some("GET"), Some("/state")) => ("200 OK", serde_json::to_string(&get_state())?),
+		_ => ("404 Not Found", String::new()),
+	};
+	let allowed_origin = origin.filter(|origin| ALLOWED_ORIGINS.contains(&origin.as_str()));
+	let cors = match allowed_origin {
+		Some(origin) => format!("Access-Control-Allow-Origin: {origin}\r\nVary: Origin\r\n"),
+		None => String::new(),
+	};
+	write!(
+		stream,
+		"HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{cors}Connection: close\r\n\r\n{body}",
+		body.len()
+	)?;
+	stream.flush()?;
+	Ok(())
+}
@@ -1,6 +1,10 @@
 use futures::{SinkExt, StreamExt};
-use shared::{Binding, BoundSwitch, InputUpdate, Layout, SwitchSlot};
-use std::collections::{BTreeMap, HashSet};
+use shared::{
+	geometry::{build_link_path, calc_combo_pos, calc_switch_pos, Bounds, Origin, Split, SWITCH_BORDER_WIDTH},
+	is_layer_active, Binding, BoundSwitch, DeadKey, DisplayFlags, InputUpdate, KeyAlias, KeySet, Layer, Layout, Link,
+	ModifierState, SwitchHighlight, SwitchShape, SwitchSlot,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use tauri_sys::event::listen;
 use wasm_bindgen::prelude::*;
 use yew::prelude::*;
@@ -37,6 +41,148 @@ fn main() {}
 struct InputState {
 	active_layers: HashSet<String>,
 	active_switches: BTreeMap<String, (Option<SwitchSlot>, wasm_timer::Instant)>,
+	tap_counts: BTreeMap<String, (usize, wasm_timer::Instant)>,
+	layer_activation_times: BTreeMap<String, wasm_timer::Instant>,
+	last_pressed: Option<LastPressed>,
+	// switches being held to activate, with a count of holds so each hold restarts its progress ring
+	pending_holds: BTreeMap<String, usize>,
+	hold_count: usize,
+	// the dead key waiting to compose a character with the next key pressed
+	pending_dead_key: Option<KeySet>,
+	// glyphs composed by dead keys, shown on the switch which completed the sequence while it is pressed
+	composed: BTreeMap<String, String>,
+	// switches which were released since they were last pressed, which briefly show a release effect
+	released: HashSet<String>,
+}
+
+/// The most recently pressed switch or combo, for the large last key readout.
+#[derive(Clone, Debug, PartialEq)]
+struct LastPressed {
+	switch_id: String,
+	slot: Option<SwitchSlot>,
+	// incremented on every press, so the readout restarts its fade even when the same switch is pressed again
+	press_index: usize,
+}
+
+impl InputState {
+	fn tap_count(&self, switch_id: &String) -> usize {
+		if !self.active_switches.contains_key(switch_id) {
+			return 0;
+		}
+		self.tap_counts
+			.get(switch_id)
+			.map(|(count, _)| *count)
+			.unwrap_or_default()
+	}
+
+	/// Tracks dead key sequences as switches are pressed, composing a glyph when a known sequence completes.
+	fn press_dead_keys(&mut self, settings: &InputSettings, switch_id: &String, slot: Option<SwitchSlot>) {
+		if settings.dead_keys.is_empty() {
+			return;
+		}
+		let Some(keys) = settings.switch_keys(&self.active_layers, switch_id, slot) else {
+			return;
+		};
+		// Modifiers change what the next key types (e.g. shift for capitals), so they don't interrupt a sequence
+		if keys.iter().all(KeyAlias::is_modifier) {
+			return;
+		}
+		match self.pending_dead_key.take() {
+			Some(dead_key) => {
+				let mut dead_keys = settings.dead_keys.iter();
+				let dead_key = dead_keys.find(|candidate| candidate.key == dead_key);
+				if let Some(glyph) = dead_key.and_then(|dead_key| dead_key.compose(keys)) {
+					self.composed.insert(switch_id.clone(), glyph.clone());
+				}
+			}
+			None => {
+				if settings.dead_keys.iter().any(|dead_key| &dead_key.key == keys) {
+					self.pending_dead_key = Some(keys.clone());
+				}
+			}
+		}
+	}
+}
+
+/// Options from the layout which are needed by the input processing task (outside of the render cycle).
+#[derive(Clone, Debug)]
+struct InputSettings {
+	tap_window: std::time::Duration,
+	min_press: std::time::Duration,
+	hold_extension: std::time::Duration,
+	layer_min_duration: Option<std::time::Duration>,
+	// how long without input before the overlay is dimmed, if the layout dims it at all
+	idle_dim_after: Option<std::time::Duration>,
+	show_typing_speed: bool,
+	dead_keys: Vec<DeadKey>,
+	// the layers in priority order (highest first), to find which keys a pressed switch types.
+	// Only needed to resolve dead keys, so this is empty when the layout has none.
+	layers: Vec<(String, Layer)>,
+	default_layer: String,
+}
+
+impl InputSettings {
+	/// The keys a switch types when pressed, as bound by the highest priority active layer which binds it.
+	fn switch_keys(
+		&self,
+		active_layers: &HashSet<String>,
+		switch_id: &String,
+		slot: Option<SwitchSlot>,
+	) -> Option<&KeySet> {
+		let suppressors = self.layers.iter().filter(|(_, layer)| layer.suppresses_default());
+		let suppressors = suppressors.map(|(id, _)| id).collect::<Vec<_>>();
+		for (layer_id, layer) in &self.layers {
+			if !is_layer_active(layer_id, &self.default_layer, active_layers, &suppressors) {
+				continue;
+			}
+			let Some(bindings) = layer.resolve_binding(switch_id) else {
+				continue;
+			};
+			let binding = bindings.slots.get(&slot.unwrap_or(SwitchSlot::Tap))?;
+			return Some(&binding.input);
+		}
+		None
+	}
+
+	/// How much longer a switch released after being held for `held` stays shown as pressed, if at all.
+	/// Presses shorter than the minimum are shown for the minimum plus the hold extension,
+	/// while anything held longer is released right away.
+	fn release_delay(&self, held: std::time::Duration) -> Option<std::time::Duration> {
+		if held >= self.min_press {
+			return None;
+		}
+		let remaining = (self.min_press + self.hold_extension).saturating_sub(held);
+		(!remaining.is_zero()).then_some(remaining)
+	}
+}
+
+impl Default for InputSettings {
+	fn default() -> Self {
+		Self::from(&Layout::default())
+	}
+}
+
+impl From<&Layout> for InputSettings {
+	fn from(layout: &Layout) -> Self {
+		Self {
+			tap_window: layout.tap_window(),
+			min_press: layout.min_press_duration(),
+			hold_extension: layout.hold_extension(),
+			layer_min_duration: layout.layer_min_duration(),
+			idle_dim_after: layout.idle_dim().map(|(_, duration)| duration),
+			show_typing_speed: layout.show_typing_speed(),
+			dead_keys: layout.dead_keys().clone(),
+			layers: match layout.dead_keys().is_empty() {
+				true => Vec::new(),
+				false => {
+					let layers = layout.layer_order().iter().rev();
+					let layers = layers.filter_map(|id| Some((id.clone(), layout.get_layer(id)?.clone())));
+					layers.collect()
+				}
+			},
+			default_layer: layout.default_layer().clone(),
+		}
+	}
 }
 
 #[function_component]
@@ -45,11 +191,39 @@ fn App() -> Html {
 	let icon_scale = use_state_eq(|| 1.0f64);
 	let layout = use_state_eq(|| None::<Layout>);
 	let input_state = use_state_eq(|| InputState::default());
+	let input_settings = use_mut_ref(|| InputSettings::default());
+	let display_flags = use_state_eq(|| DisplayFlags::default());
+	let config_error = use_state_eq(|| None::<String>);
+	// the most recent effect emitted by a binding's `on_press`, and how many effects have been emitted (to restart animations)
+	let effect = use_state_eq(|| None::<(String, usize)>);
+	// switches highlighted by the `highlight_switch` command, independent of real input
+	let highlighted = use_state_eq(|| HashSet::<String>::new());
+	let modifiers = use_state_eq(|| ModifierState::default());
+	// the layers the active display profile shows, or empty to show all layers
+	let profile_layers = use_state_eq(|| Vec::<String>::new());
+	// the same allowlist, for the input processing task (outside of the render cycle)
+	let profile_layers_ref = use_mut_ref(|| Vec::<String>::new());
+	// how many times each switch has been clicked while previewing the layout, to cycle through the layers which bind it
+	let preview_clicks = use_state_eq(|| HashMap::<String, usize>::new());
+	// true once there has been no input for long enough that the overlay is dimmed
+	let idle = use_state_eq(|| false);
+	// the rolling typing speed, in words per minute, when the layout shows it
+	let typing_wpm = use_state_eq(|| 0u32);
 
 	let window_size_handle = window_size.clone();
 	let icon_scale_handle = icon_scale.clone();
 	let layout_handle = layout.clone();
 	let input_handle = input_state.clone();
+	let input_settings_handle = input_settings.clone();
+	let display_flags_handle = display_flags.clone();
+	let config_error_handle = config_error.clone();
+	let effect_handle = effect.clone();
+	let highlighted_handle = highlighted.clone();
+	let modifiers_handle = modifiers.clone();
+	let profile_layers_handle = profile_layers.clone();
+	let profile_layers_ref_handle = profile_layers_ref.clone();
+	let idle_handle = idle.clone();
+	let typing_wpm_handle = typing_wpm.clone();
 	use_mount(move || {
 		if !is_bound() {
 			log::debug!("ignoring event listeners");
@@ -57,6 +231,11 @@ fn App() -> Html {
 		}
 		log::debug!("mounting event listeners");
 
+		// the mirror window is a window capture source, so it has a solid background instead of being transparent
+		if tauri_sys::window::current_window().label() == shared::MIRROR_WINDOW_LABEL {
+			let _ = gloo_utils::body().class_list().add_1("mirror");
+		}
+
 		let window_size = window_size_handle.clone();
 		let icon_scale = icon_scale_handle.clone();
 		spawn_local("recv::scale", async move {
@@ -71,15 +250,100 @@ fn App() -> Html {
 		});
 
 		let layout = layout_handle.clone();
+		let input_settings = input_settings_handle.clone();
 		spawn_local("recv::layout", async move {
 			let mut stream = listen::<Layout>("layout").await?;
 			while let Some(event) = stream.next().await {
 				//log::debug!(target: "recv::layout", "layout update: {:?}", event.payload);
+				// layers missing from the order would silently never be drawn, so keep showing the previous layout
+				if let Err(err) = event.payload.validate_layer_order() {
+					log::error!(target: "recv::layout", "{err}");
+					continue;
+				}
+				*input_settings.borrow_mut() = InputSettings::from(&event.payload);
 				layout.set(Some(event.payload));
 			}
 			Ok(()) as anyhow::Result<()>
 		});
 
+		let display_flags = display_flags_handle.clone();
+		spawn_local("recv::flags", async move {
+			let mut stream = listen::<DisplayFlags>("flags").await?;
+			while let Some(event) = stream.next().await {
+				display_flags.set(event.payload);
+			}
+			Ok(()) as anyhow::Result<()>
+		});
+
+		let config_error = config_error_handle.clone();
+		spawn_local("recv::config_error", async move {
+			let mut stream = listen::<Option<String>>("config_error").await?;
+			while let Some(event) = stream.next().await {
+				config_error.set(event.payload);
+			}
+			Ok(()) as anyhow::Result<()>
+		});
+
+		let effect = effect_handle.clone();
+		spawn_local("recv::effect", async move {
+			let mut stream = listen::<String>("effect").await?;
+			let mut effect_index = 0usize;
+			while let Some(event) = stream.next().await {
+				effect_index += 1;
+				effect.set(Some((event.payload, effect_index)));
+			}
+			Ok(()) as anyhow::Result<()>
+		});
+
+		let profile_layers = profile_layers_handle.clone();
+		let profile_layers_ref = profile_layers_ref_handle.clone();
+		spawn_local("recv::profile_layers", async move {
+			let mut stream = listen::<Vec<String>>("profile_layers").await?;
+			while let Some(event) = stream.next().await {
+				*profile_layers_ref.borrow_mut() = event.payload.clone();
+				profile_layers.set(event.payload);
+			}
+			Ok(()) as anyhow::Result<()>
+		});
+
+		let modifiers = modifiers_handle.clone();
+		spawn_local("recv::modifiers", async move {
+			let mut stream = listen::<ModifierState>("modifiers").await?;
+			while let Some(event) = stream.next().await {
+				modifiers.set(event.payload);
+			}
+			Ok(()) as anyhow::Result<()>
+		});
+
+		let highlighted = highlighted_handle.clone();
+		spawn_local("recv::highlight", async move {
+			// the most recent highlight of each switch, so an older highlight expiring doesn't end a newer one
+			let highlights = std::rc::Rc::new(std::cell::RefCell::new(HashMap::<String, usize>::new()));
+			let mut highlight_index = 0usize;
+			let mut stream = listen::<SwitchHighlight>("highlight").await?;
+			while let Some(event) = stream.next().await {
+				let SwitchHighlight { switch_id, duration_ms } = event.payload;
+				highlight_index += 1;
+				let index = highlight_index;
+				highlights.borrow_mut().insert(switch_id.clone(), index);
+				highlighted.set(highlights.borrow().keys().cloned().collect());
+
+				let highlights = highlights.clone();
+				let highlighted = highlighted.clone();
+				spawn_local("highlight::expire", async move {
+					let duration_ms = duration_ms.min(u32::MAX as u64) as u32;
+					gloo_timers::future::TimeoutFuture::new(duration_ms).await;
+					let mut highlights = highlights.borrow_mut();
+					if highlights.get(&switch_id) == Some(&index) {
+						highlights.remove(&switch_id);
+						highlighted.set(highlights.keys().cloned().collect());
+					}
+					Ok(()) as anyhow::Result<()>
+				});
+			}
+			Ok(()) as anyhow::Result<()>
+		});
+
 		let (send_input, mut recv_input) = futures::channel::mpsc::unbounded::<InputUpdate>();
 
 		spawn_local("input::recv", {
@@ -88,28 +352,116 @@ fn App() -> Html {
 				let mut stream = listen::<InputUpdate>("input").await?;
 				while let Some(event) = stream.next().await {
 					//log::debug!(target: "recv::input", "update: {:?}", event.payload);
-					send_input.send(event.payload).await?;
+					for update in event.payload.flatten() {
+						send_input.send(update).await?;
+					}
 				}
 				Ok(()) as anyhow::Result<()>
 			}
 		});
 
 		let input_state = input_handle.clone();
+		let input_settings = input_settings_handle.clone();
+		let profile_layers = profile_layers_ref_handle.clone();
+		let idle = idle_handle.clone();
+		let typing_wpm = typing_wpm_handle.clone();
 		spawn_local("input::process", async move {
-			static MIN_PRESS_DURATION: std::time::Duration = std::time::Duration::from_millis(100);
 			let mut local_state = InputState::default();
+			// when recent switches were pressed, and how many presses there have been, to measure typing speed
+			let mut press_times = std::collections::VecDeque::<wasm_timer::Instant>::new();
+			let press_count = std::rc::Rc::new(std::cell::Cell::new(0usize));
+			// the number of updates processed, so an idle timer knows if there has been input since it started
+			let update_count = std::rc::Rc::new(std::cell::Cell::new(0usize));
 			while let Some(update) = recv_input.next().await {
 				match update {
 					InputUpdate::LayerActivate(layer) => {
+						// Layers hidden by the display profile are never tracked as active
+						if !is_layer_visible(&profile_layers.borrow(), &layer) {
+							continue;
+						}
+						local_state
+							.layer_activation_times
+							.insert(layer.clone(), wasm_timer::Instant::now());
 						local_state.active_layers.insert(layer);
 					}
 					InputUpdate::LayerDeactivate(layer) => {
-						local_state.active_layers.remove(&layer);
+						// Layers can be kept visible for a minimum duration so quick taps don't flicker the overlay
+						let min_duration = input_settings.borrow().layer_min_duration;
+						let activated_at = local_state.layer_activation_times.get(&layer);
+						let latent_remove_duration = match (min_duration, activated_at) {
+							(Some(min_duration), Some(start_time)) => {
+								let duration_since_activated = wasm_timer::Instant::now().duration_since(*start_time);
+								let duration_remaining = min_duration.saturating_sub(duration_since_activated);
+								(!duration_remaining.is_zero()).then_some(duration_remaining)
+							}
+							_ => None,
+						};
+
+						match latent_remove_duration {
+							None => {
+								local_state.layer_activation_times.remove(&layer);
+								local_state.active_layers.remove(&layer);
+							}
+							Some(duration_remaining) => {
+								let mut send_input = send_input.clone();
+								spawn_local("recv::input::latent_deactivate", async move {
+									gloo_timers::future::TimeoutFuture::new(duration_remaining.as_millis() as u32)
+										.await;
+									send_input.send(InputUpdate::LayerDeactivate(layer)).await?;
+									Ok(()) as anyhow::Result<()>
+								});
+								continue;
+							}
+						}
+					}
+					InputUpdate::HoldStarted(switch_id) => {
+						local_state.hold_count += 1;
+						let hold_index = local_state.hold_count;
+						local_state.pending_holds.insert(switch_id, hold_index);
+					}
+					InputUpdate::HoldCancelled(switch_id) => {
+						local_state.pending_holds.remove(&switch_id);
 					}
 					InputUpdate::SwitchPressed(switch_id, slot) => {
-						local_state
-							.active_switches
-							.insert(switch_id, (slot, wasm_timer::Instant::now()));
+						let now = wasm_timer::Instant::now();
+						local_state.pending_holds.remove(&switch_id);
+						// Presses in quick succession accumulate a tap count, anything slower starts over
+						let tap_window = input_settings.borrow().tap_window;
+						let tap_count = match local_state.tap_counts.get(&switch_id) {
+							Some((count, last_pressed)) if now.duration_since(*last_pressed) <= tap_window => count + 1,
+							_ => 1,
+						};
+						local_state.tap_counts.insert(switch_id.clone(), (tap_count, now));
+						let press_index = local_state.last_pressed.as_ref().map(|last| last.press_index + 1);
+						local_state.last_pressed = Some(LastPressed {
+							switch_id: switch_id.clone(),
+							slot,
+							press_index: press_index.unwrap_or_default(),
+						});
+						local_state.press_dead_keys(&input_settings.borrow(), &switch_id, slot);
+						// Typing speed is the rate of presses over a short window, dropping to zero once typing stops
+						if input_settings.borrow().show_typing_speed {
+							press_times.push_back(now);
+							let is_stale = |time: &wasm_timer::Instant| now.duration_since(*time) > TYPING_SPEED_WINDOW;
+							while press_times.front().is_some_and(is_stale) {
+								press_times.pop_front();
+							}
+							typing_wpm.set(words_per_minute(press_times.len()));
+
+							press_count.set(press_count.get() + 1);
+							let press_index = press_count.get();
+							let press_count = press_count.clone();
+							let typing_wpm = typing_wpm.clone();
+							spawn_local("input::typing_speed", async move {
+								gloo_timers::future::TimeoutFuture::new(TYPING_SPEED_WINDOW.as_millis() as u32).await;
+								if press_count.get() == press_index {
+									typing_wpm.set(0);
+								}
+								Ok(()) as anyhow::Result<()>
+							});
+						}
+						local_state.released.remove(&switch_id);
+						local_state.active_switches.insert(switch_id, (slot, now));
 					}
 					InputUpdate::SwitchReleased(switch_id) => {
 						let latent_remove_duration = match local_state.active_switches.get(&switch_id) {
@@ -117,14 +469,15 @@ fn App() -> Html {
 							Some((_slot, start_time)) => {
 								let now = wasm_timer::Instant::now();
 								let duration_since_pressed = now.duration_since(*start_time);
-								let duration_remaining = MIN_PRESS_DURATION.saturating_sub(duration_since_pressed);
-								(!duration_remaining.is_zero()).then_some(duration_remaining)
+								input_settings.borrow().release_delay(duration_since_pressed)
 							}
 						};
 
 						match latent_remove_duration {
 							None => {
 								local_state.active_switches.remove(&switch_id);
+								local_state.composed.remove(&switch_id);
+								local_state.released.insert(switch_id);
 							}
 							Some(duration_remaining) => {
 								let mut send_input = send_input.clone();
@@ -138,142 +491,329 @@ fn App() -> Html {
 							}
 						}
 					}
+					// batches are expanded by `input::recv` before being sent for processing
+					InputUpdate::Batch(_) => continue,
 				}
 				input_state.set(local_state.clone());
+
+				// Any input returns the overlay to full opacity, and it dims again once the input stops for long enough
+				update_count.set(update_count.get() + 1);
+				idle.set(false);
+				if let Some(idle_dim_after) = input_settings.borrow().idle_dim_after {
+					let update_index = update_count.get();
+					let update_count = update_count.clone();
+					let idle = idle.clone();
+					spawn_local("input::idle_dim", async move {
+						gloo_timers::future::TimeoutFuture::new(idle_dim_after.as_millis() as u32).await;
+						if update_count.get() == update_index {
+							idle.set(true);
+						}
+						Ok(()) as anyhow::Result<()>
+					});
+				}
 			}
 			Ok(()) as anyhow::Result<()>
 		});
 
+		// Ctrl+Shift+R reloads the config from disk while iterating on a layout.
+		// Only in debug builds, so the shortcut can't interfere with the overlay when in use.
+		if cfg!(debug_assertions) {
+			let window = gloo_utils::window();
+			gloo_events::EventListener::new(&window, "keydown", |event| {
+				let Some(event) = event.dyn_ref::<web_sys::KeyboardEvent>() else {
+					return;
+				};
+				if event.ctrl_key() && event.shift_key() && event.code() == "KeyR" {
+					event.prevent_default();
+					spawn_local("reload_config", tauri_sys::event::emit("reload_config", &()));
+				}
+			})
+			.forget();
+		}
+
 		spawn_local("ready", tauri_sys::event::emit("ready", &()));
 	});
 
-	let layout_style = Style::default().with("--icon-scale", *icon_scale);
+	let mut layout_style = Style::default().with("--icon-scale", *icon_scale);
+	if let Some(layout) = layout.as_ref() {
+		let key_colors = layout.key_colors();
+		if let Some(color) = &key_colors.layer {
+			layout_style.insert("--layer-key-color", color);
+		}
+		if let Some(color) = &key_colors.action {
+			layout_style.insert("--action-key-color", color);
+		}
+	}
+	let origin = layout.as_ref().map(Layout::origin).unwrap_or_default();
+	let show_modifiers = layout.as_ref().is_some_and(Layout::show_modifiers);
+	let show_mascot = layout.as_ref().is_some_and(Layout::show_mascot);
+	let show_guidelines = layout.as_ref().map_or(true, Layout::show_guidelines);
+	let wrap_labels = layout.as_ref().is_some_and(Layout::wrap_labels);
+	let show_typing_speed = layout.as_ref().is_some_and(Layout::show_typing_speed);
+	if show_typing_speed {
+		let intensity = (*typing_wpm as f64 / FULL_INTENSITY_WPM).min(1.0);
+		layout_style.insert("--typing-intensity", intensity);
+	}
+	let idle_dim_opacity = layout.as_ref().and_then(Layout::idle_dim).map(|(opacity, _)| opacity);
+	let overlay_class = classes!("overlay", (*idle && idle_dim_opacity.is_some()).then_some("idle"));
+	let overlay_style = Style::default().with("--idle-opacity", idle_dim_opacity.unwrap_or(1.0));
+	let last_key_duration = layout.as_ref().map(Layout::last_key_duration).unwrap_or_default();
+	let last_key_style = Style::default().with("--duration", format!("{}ms", last_key_duration.as_millis()));
 	//log::debug!("{:?}", *input_state);
 
 	let mut switches = Vec::with_capacity(40);
 	let mut combos = Vec::with_capacity(10);
+	let mut last_key = None;
+	let mut layer_color = None;
 	if let Some(layout) = layout.as_ref() {
-		'switch: for (switch_id, switch) in layout.switches().iter() {
+		let last_pressed = input_state.last_pressed.as_ref().filter(|_| layout.show_last_key());
+		// Layers which are active and not hidden by the display profile
+		let is_layer_shown = |layer_id: &String| {
+			layout.is_layer_active(layer_id, &input_state.active_layers) && is_layer_visible(&profile_layers, layer_id)
+		};
+		// The highest priority active layer, whose bindings are emphasized when lower layers are dimmed
+		let top_layer = layout
+			.layer_order()
+			.iter()
+			.rev()
+			.find(|layer_id| is_layer_shown(layer_id));
+		let dim_lower_layers = layout.dim_inactive() && top_layer.is_some_and(|id| id != layout.default_layer());
+		// The whole overlay is outlined in the color of the highest priority active layer, unless it's the default layer
+		let outlined_layer = top_layer.filter(|id| *id != layout.default_layer());
+		let outlined_layer = outlined_layer.and_then(|id| layout.get_layer(id));
+		layer_color = outlined_layer.and_then(Layer::color).cloned();
+		// Switches which make up an active combo are highlighted along with the combo itself
+		let active_combo_members = layout
+			.combos()
+			.iter()
+			.filter(|combo| input_state.active_switches.contains_key(&combo.id))
+			.flat_map(|combo| combo.member_switches())
+			.collect::<HashSet<_>>();
+		'switch: for (switch_id, layout_switch) in layout.switches().iter() {
+			let switch = &layout.switch_placement(switch_id, layout_switch);
+			// When previewing outside of the app, clicking a switch shows the binding of the next layer which binds it
+			let preview_layer = preview_clicks.get(switch_id).and_then(|clicks| {
+				let mut bound_layers = layout.layer_order().iter().filter(|layer_id| {
+					let layer = layout.get_layer(layer_id);
+					layer.is_some_and(|layer| layer.get_binding(switch_id).is_some())
+				});
+				let count = bound_layers.clone().count();
+				bound_layers.nth(clicks % count.max(1))
+			});
+			let onclick = (!is_bound()).then(|| {
+				let preview_clicks = preview_clicks.clone();
+				let switch_id = switch_id.clone();
+				Callback::from(move |_: MouseEvent| {
+					let mut clicks = (*preview_clicks).clone();
+					*clicks.entry(switch_id.clone()).or_default() += 1;
+					preview_clicks.set(clicks);
+				})
+			});
+			// When showing all layers, every layer's binding is listed instead of resolving the active binding
+			if display_flags.all_layers {
+				let layer_bindings = layout.layer_order().iter().filter_map(|layer_id| {
+					if !is_layer_visible(&profile_layers, layer_id) {
+						return None;
+					}
+					let bindings = layout.get_layer(layer_id)?.get_binding(switch_id)?;
+					Some((AttrValue::from(layer_id.clone()), bindings.clone()))
+				});
+				switches.push(html!(<LayerStackSwitch
+					window_size={*window_size}
+					origin={layout.origin()}
+					split={layout.split()}
+					shape={layout.switch_shape(switch)}
+					switch_id={switch_id.clone()}
+					switch={switch.clone()}
+					layer_bindings={layer_bindings.collect::<Vec<_>>()}
+				/>));
+				continue 'switch;
+			}
+
 			for layer_id in layout.layer_order().iter().rev() {
-				if !input_state.active_layers.contains(layer_id) {
+				let shown = match preview_layer {
+					Some(preview_layer) => preview_layer == layer_id,
+					None => is_layer_shown(layer_id),
+				};
+				if !shown {
 					continue;
 				}
 				let Some(layer) = layout.get_layer(layer_id) else {
 					continue;
 				};
-				let Some(bindings) = layer.get_binding(switch_id) else {
+				let Some(bindings) = layer.resolve_binding(switch_id) else {
 					continue;
 				};
+				// The layer which supplies the binding can also move or resize the switch
+				let switch = &layout.switch_placement(switch_id, &bindings.override_switch(layout_switch));
 				let active_slot = input_state.active_switches.get(switch_id);
 				let active_slot = active_slot.map(|(slot, _start_time)| slot.clone()).flatten();
 
+				// A dead key sequence completed by this switch shows the composed glyph instead of the key's own legend
+				let mut bindings = bindings.clone();
+				// While shift is held, bindings show their shifted display, like the upper legend of a real keycap
+				if modifiers.shift {
+					for binding in bindings.slots.values_mut() {
+						if let Some(shifted) = binding.shifted.take() {
+							binding.display = Some(shifted);
+						}
+					}
+				}
+				if let Some(glyph) = input_state.composed.get(switch_id) {
+					if let Some(binding) = bindings.slots.get_mut(&active_slot.unwrap_or(SwitchSlot::Tap)) {
+						binding.display = Some(shared::BindingDisplay::Text(glyph.clone()));
+					}
+				}
+
+				if let Some(last_pressed) = last_pressed.filter(|last| &last.switch_id == switch_id) {
+					let slot = last_pressed.slot.unwrap_or(SwitchSlot::Tap);
+					if let Some(binding) = bindings.slots.get(&slot) {
+						last_key = Some((last_pressed.press_index, binding_element(binding)));
+					}
+				}
+
+				// When previewing outside of the app, hovering a switch inspects it
+				let info = (!is_bound()).then(|| {
+					let layer_bindings = layout.layer_order().iter().filter_map(|layer_id| {
+						let bindings = layout.get_layer(layer_id)?.get_binding(switch_id)?;
+						Some((AttrValue::from(layer_id.clone()), bindings.clone()))
+					});
+					SwitchInfo {
+						pos: layout_switch.pos,
+						layer_bindings: layer_bindings.collect(),
+					}
+				});
+
 				switches.push(html!(<KeySwitch
 					window_size={*window_size}
+					origin={layout.origin()}
+					split={layout.split()}
+					shape={layout.switch_shape(switch)}
 					switch_id={switch_id.clone()}
-					switch={*switch}
-					bindings={bindings.clone()}
+					switch={switch.clone()}
+					bindings={bindings}
 					active_slot={active_slot}
+					tap_count={input_state.tap_count(switch_id)}
+					source_layer={layer_id.clone()}
+					dimmed={dim_lower_layers && top_layer != Some(layer_id)}
+					combo_member_active={active_combo_members.contains(switch_id)}
+					highlighted={highlighted.contains(switch_id)}
+					slot_divider={layout.show_slot_divider()}
+					released={input_state.released.contains(switch_id)}
+					{onclick}
+					{info}
 				/>));
 
 				continue 'switch;
 			}
+
+			// No active layer binds this switch
+			if layout.show_unbound() {
+				switches.push(html!(<PlaceholderSwitch
+					window_size={*window_size}
+					origin={layout.origin()}
+					split={layout.split()}
+					shape={layout.switch_shape(switch)}
+					switch_id={switch_id.clone()}
+					switch={switch.clone()}
+				/>));
+			}
 		}
 		'combo: for combo in layout.combos().iter() {
 			// Filter out combos that are not on an active layer
 			if !combo.layers.is_empty() {
-				let on_active_layer = combo
-					.layers
-					.iter()
-					.any(|layer| input_state.active_layers.contains(layer));
+				let on_active_layer = combo.layers.iter().any(is_layer_shown);
 				if !on_active_layer {
 					continue 'combo;
 				}
 			}
 
+			let label = combo.active_label(layout.layer_order(), is_layer_shown);
+			if let Some(last_pressed) = last_pressed.filter(|last| last.switch_id == combo.id) {
+				last_key = Some((
+					last_pressed.press_index,
+					html!(<BindingDisplay binding={label.clone()} />),
+				));
+			}
+
 			let mut class = classes!("switch", "combo");
-			let size = 30f64;
-			let pos = (combo.pos.0 as f64, combo.pos.1 as f64);
-			let pos = calculate_screen_pos(&*window_size, pos, size);
+			let pos = calc_combo_pos(&*window_size, layout.origin(), layout.split(), combo);
+			let outer_size = combo.size() as f32 + 2.0 * SWITCH_BORDER_WIDTH as f32;
+			let border_radius = combo.shape().border_radius(outer_size);
 			let style = Style::from([
 				("--x", format!("{}px", pos.0)),
 				("--y", format!("{}px", pos.1)),
-				("width", format!("{size}px")),
-				("height", format!("{size}px")),
+				("width", format!("{}px", combo.size())),
+				("height", format!("{}px", combo.size())),
 				("border-width", format!("{SWITCH_BORDER_WIDTH}px")),
+				("border-radius", format!("{border_radius}px")),
+				("--rotation", format!("{}deg", layout.split().rotation(combo.side))),
 			]);
 
 			if input_state.active_switches.contains_key(&combo.id) {
 				class.push("active");
 			}
+			// A ring which fills over the hold duration, while the combo is being held to activate
+			let hold_ring = match (combo.hold_ms, input_state.pending_holds.get(&combo.id)) {
+				(Some(hold_ms), Some(hold_index)) => {
+					let style = Style::default().with("--hold-duration", format!("{hold_ms}ms"));
+					Some(html!(<div class="hold-ring" key={*hold_index} {style} />))
+				}
+				_ => None,
+			};
 
 			let mut svg_link_paths = Vec::new();
-			'link: for link in &combo.links {
-				let mut path = ComboLinkPath::default();
-				for point in link.points() {
-					match point {
-						shared::LinkPoint::Switch(switch_id, rel_x, rel_y) => match layout.switches().get(switch_id) {
-							None => {
-								log::error!(target: "combo", "failed to draw link for combo {}, invalid switch id {}", combo.id, switch_id);
-								continue 'link;
-							}
-							Some(switch) => {
-								let half_size = switch.size() as f64 * 0.5 + SWITCH_BORDER_WIDTH as f64;
-								// get the top-left pos
-								let pos = calc_switch_pos(&*window_size, switch);
-								let mut pos = (pos.0 as f64, pos.1 as f64);
-								// center the coords
-								pos.0 += half_size;
-								pos.1 += half_size;
-								// apply relative offset
-								pos.0 += rel_x * half_size;
-								pos.1 += rel_y * half_size;
-								path.push(pos);
-							}
-						},
-						shared::LinkPoint::Point {
-							pos,
-							control_dirs,
-							control_incoming_axis,
-							control_size,
-						} => {
-							let control = (
-								(window_size.0 as f64 * 0.5) + pos.0,
-								(window_size.1 as f64 * 0.5) - pos.1,
-							);
-							let mut a = control;
-							let mut b = control;
-							if *control_incoming_axis == 0 {
-								a.0 += *control_size * control_dirs.0;
-								b.1 += *control_size * control_dirs.1;
-							} else {
-								a.1 += *control_size * control_dirs.1;
-								b.0 += *control_size * control_dirs.0;
-							}
-							path.push_curve(a, control, b);
+			let mut link_bounds = None::<Bounds>;
+			let arrow_id = format!("{}-arrow", combo.id);
+			for link in &combo.links {
+				match build_link_path(layout, &*window_size, combo, link) {
+					Ok(path) => {
+						if let Some(bounds) = path.bounds() {
+							link_bounds = Some(link_bounds.map_or(bounds, |link_bounds| link_bounds.union(bounds)));
 						}
-						shared::LinkPoint::Anchor(rel_x, rel_y) => {
-							let half_size = size * 0.5 + (SWITCH_BORDER_WIDTH as f64);
-							let mut pos = pos;
-							// center the coords
-							pos.0 += half_size;
-							pos.1 += half_size;
-							// apply relative offset
-							pos.0 += rel_x * half_size;
-							pos.1 += rel_y * half_size;
-							path.push(pos);
-						}
-					};
+						let marker_end = link.arrow().then(|| format!("url(#{arrow_id})"));
+						let dash = link.dash().cloned();
+						svg_link_paths.push(html!(<path
+							d={path.to_string()}
+							stroke="white"
+							stroke-width="2"
+							fill="none"
+							marker-end={marker_end}
+							stroke-dasharray={dash}
+						/>));
+					}
+					Err(err) => {
+						log::error!(target: "combo", "{err}");
+					}
 				}
-				svg_link_paths.push(html!(<path d={path.to_string()} stroke="white" stroke-width="2" fill="none" />));
 			}
-			let svg_link = (!svg_link_paths.is_empty())
-				.then(|| html!(<svg id={combo.id.clone()} class="link">{svg_link_paths}</svg>));
+			// The svg covers all of its links, even those which extend outside of the window, so none are clipped.
+			// Its view box matches where it is placed, so the paths are drawn in window coordinates.
+			let svg_link = link_bounds.map(|bounds| {
+				// include the width of the stroke, which is centered on the path
+				let bounds = bounds.expand(1.0);
+				let (width, height) = bounds.size();
+				let style = Style::from([
+					("left", format!("{}px", bounds.min.0)),
+					("top", format!("{}px", bounds.min.1)),
+					("width", format!("{width}px")),
+					("height", format!("{height}px")),
+				]);
+				let view_box = format!("{} {} {width} {height}", bounds.min.0, bounds.min.1);
+				let arrow_marker = combo
+					.links
+					.iter()
+					.any(Link::arrow)
+					.then(|| html!(<defs>{arrow_marker(&arrow_id)}</defs>));
+				html!(<svg id={combo.id.clone()} class="link" {style} viewBox={view_box}>{arrow_marker}{svg_link_paths}</svg>)
+			});
 
 			combos.push(html!(<>
 				<div id={combo.id.clone()} {class} {style}>
 					<div class={classes!("slot", "center")}>
-						<BindingDisplay binding={combo.label.clone()} />
+						<BindingDisplay binding={label.clone()} />
 					</div>
+					{hold_ring}
 				</div>
 				{svg_link}
 			</>));
@@ -281,171 +821,197 @@ fn App() -> Html {
 	}
 
 	html! {<>
-		<div class="guideline x" />
-		<div class="guideline y" />
-		<div style="display: none;"><img src="https://raw.githubusercontent.com/tapioki/cephalopoda/main/Images/architeuthis_dux.png" style="height: 400px; margin-left: -150px; margin-top: 100px;" /></div>
-		<div style={layout_style}>
-			{switches}
-			{combos}
+		{config_error.as_ref().map(|error| html!(
+			<div class="config-error">
+				<div class="title">{"The config could not be loaded. Open the config folder from the tray to fix it."}</div>
+				<div class="message">{error}</div>
+			</div>
+		))}
+		<div class={overlay_class} style={overlay_style}>
+			{show_guidelines.then(|| html!(<>
+				<div class="guideline x" />
+				<div class="guideline y" />
+			</>))}
+			{show_mascot.then(|| html!(
+				<div><img src="https://raw.githubusercontent.com/tapioki/cephalopoda/main/Images/architeuthis_dux.png" style="height: 400px; margin-left: -150px; margin-top: 100px;" /></div>
+			))}
+			<div class={classes!(wrap_labels.then_some("wrap-labels"))} style={layout_style}>
+				{layer_color.map(|color| html!(
+					<div class="layer-outline" style={Style::default().with("--layer-color", color)} />
+				))}
+				{display_flags.design_grid.then(|| html!(<DesignGrid window_size={*window_size} {origin} />))}
+				{switches}
+				{combos}
+				{effect.as_ref().map(|(name, effect_index)| html!(
+					<div class="effect" key={*effect_index} name={name.clone()} />
+				))}
+				{last_key.map(|(press_index, element)| html!(
+					<div class="last-key" key={press_index} style={last_key_style.clone()}>{element}</div>
+				))}
+			</div>
+			{show_modifiers.then(|| html!(<ModifierIndicators modifiers={*modifiers} />))}
+			{show_typing_speed.then(|| html!(<div class="typing-speed">{format!("{} wpm", *typing_wpm)}</div>))}
 		</div>
 	</>}
 }
 
-fn segment_abs(segment: &svgtypes::PathSegment) -> bool {
-	use svgtypes::PathSegment::*;
-	match segment {
-		MoveTo { abs, .. } => *abs,
-		LineTo { abs, .. } => *abs,
-		HorizontalLineTo { abs, .. } => *abs,
-		VerticalLineTo { abs, .. } => *abs,
-		CurveTo { abs, .. } => *abs,
-		SmoothCurveTo { abs, .. } => *abs,
-		Quadratic { abs, .. } => *abs,
-		SmoothQuadratic { abs, .. } => *abs,
-		EllipticalArc { abs, .. } => *abs,
-		ClosePath { abs, .. } => *abs,
-	}
+#[derive(Clone, PartialEq, Properties)]
+pub struct ModifierIndicatorsProps {
+	pub modifiers: ModifierState,
 }
-fn segment_type_id(segment: &svgtypes::PathSegment) -> &'static str {
-	use svgtypes::PathSegment::*;
-	match segment {
-		MoveTo { .. } => "m",
-		LineTo { .. } => "l",
-		HorizontalLineTo { .. } => "h",
-		VerticalLineTo { .. } => "v",
-		CurveTo { .. } => "c",
-		SmoothCurveTo { .. } => "s",
-		Quadratic { .. } => "q",
-		SmoothQuadratic { .. } => "t",
-		EllipticalArc { .. } => "a",
-		ClosePath { .. } => "z",
-	}
+
+/// A strip of indicators for held modifiers and toggled locks, shown even if those keys are not in the layout.
+#[function_component]
+fn ModifierIndicators(ModifierIndicatorsProps { modifiers }: &ModifierIndicatorsProps) -> Html {
+	let indicators = [
+		("Shift", modifiers.shift),
+		("Ctrl", modifiers.ctrl),
+		("Alt", modifiers.alt),
+		("Meta", modifiers.meta),
+		("Caps", modifiers.caps_lock),
+		("Num", modifiers.num_lock),
+		("Scroll", modifiers.scroll_lock),
+	];
+	let indicators = indicators.into_iter().map(|(label, on)| {
+		let class = classes!("indicator", on.then_some("on"));
+		html!(<div {class}>{label}</div>)
+	});
+	html!(<div class="modifiers">{for indicators}</div>)
 }
-fn segment_id(segment: &svgtypes::PathSegment) -> String {
-	let id = segment_type_id(segment);
-	if segment_abs(segment) {
-		id.to_uppercase()
-	} else {
-		id.to_owned()
-	}
+
+/// Returns true if the layer is in the display profile's allowlist, where an empty allowlist shows all layers.
+fn is_layer_visible(allowlist: &[String], layer: &str) -> bool {
+	allowlist.is_empty() || allowlist.iter().any(|id| id == layer)
 }
-fn segment_display(segment: &svgtypes::PathSegment, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-	use svgtypes::PathSegment::*;
-	write!(f, "{}", segment_id(segment))?;
-	match segment {
-		MoveTo { x, y, .. } => write!(f, " {x} {y}")?,
-		LineTo { x, y, .. } => write!(f, " {x} {y}")?,
-		HorizontalLineTo { x, .. } => write!(f, " {x}")?,
-		VerticalLineTo { y, .. } => write!(f, " {y}")?,
-		CurveTo {
-			x1, y1, x2, y2, x, y, ..
-		} => write!(f, " {x1} {y1} {x2} {y2} {x} {y}")?,
-		SmoothCurveTo { x2, y2, x, y, .. } => write!(f, " {x2} {y2} {x} {y}")?,
-		Quadratic { x1, y1, x, y, .. } => write!(f, " {x1} {y1} {x} {y}")?,
-		SmoothQuadratic { x, y, .. } => write!(f, " {x} {y}")?,
-		EllipticalArc {
-			rx,
-			ry,
-			x_axis_rotation,
-			large_arc,
-			sweep,
-			x,
-			y,
-			..
-		} => {
-			write!(f, " {rx} {ry} {x_axis_rotation}")?;
-			write!(f, " {}", if *large_arc { 1 } else { 0 })?;
-			write!(f, " {}", if *sweep { 1 } else { 0 })?;
-			write!(f, " {x} {y}")?;
-		}
-		ClosePath { .. } => {}
-	}
-	Ok(())
+
+static DESIGN_GRID_SPACING: i64 = 50;
+
+/// The arrowhead drawn at the end of combo links with `arrow`, referenced by the links of a combo by its `id`.
+fn arrow_marker(id: &str) -> Html {
+	html!(
+		<marker id={id.to_owned()} viewBox="0 0 10 10" refX="8" refY="5" markerWidth="6" markerHeight="6" orient="auto-start-reverse">
+			<path d="M 0 0 L 10 5 L 0 10 z" fill="white" />
+		</marker>
+	)
 }
 
-#[derive(Default)]
-struct ComboLinkPath(Vec<svgtypes::PathSegment>);
-impl ComboLinkPath {
-	fn push(&mut self, pos: (f64, f64)) {
-		if self.0.is_empty() {
-			self.0.push(svgtypes::PathSegment::MoveTo {
-				abs: true,
-				x: pos.0,
-				y: pos.1,
-			});
-		} else {
-			self.0.push(svgtypes::PathSegment::LineTo {
-				abs: true,
-				x: pos.0,
-				y: pos.1,
-			});
-		}
-	}
+// typing speed is measured over the presses in this window
+static TYPING_SPEED_WINDOW: std::time::Duration = std::time::Duration::from_secs(5);
+// the typing speed at which pressed switches glow their brightest
+static FULL_INTENSITY_WPM: f64 = 120.0;
 
-	fn push_curve(&mut self, a: (f64, f64), control: (f64, f64), b: (f64, f64)) {
-		self.0.push(svgtypes::PathSegment::LineTo {
-			abs: true,
-			x: a.0,
-			y: a.1,
-		});
-		self.0.push(svgtypes::PathSegment::Quadratic {
-			abs: true,
-			x1: control.0,
-			y1: control.1,
-			x: b.0,
-			y: b.1,
-		});
-	}
+/// The typing speed for a number of presses within the `TYPING_SPEED_WINDOW`, counting 5 presses as a word.
+fn words_per_minute(presses: usize) -> u32 {
+	let words = presses as f64 / 5.0;
+	(words * 60.0 / TYPING_SPEED_WINDOW.as_secs_f64()).round() as u32
 }
-impl std::fmt::Display for ComboLinkPath {
-	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		for i in 0..self.0.len() {
-			segment_display(&self.0[i], f)?;
-			if i != self.0.len() - 1 {
-				write!(f, " ")?;
-			}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct DesignGridProps {
+	pub window_size: (u32, u32),
+	pub origin: Origin,
+}
+
+/// Grid lines labeled in layout coordinates (according to the layout's origin),
+/// so positions for switches and combos can be read directly off the overlay.
+#[function_component]
+fn DesignGrid(DesignGridProps { window_size, origin }: &DesignGridProps) -> Html {
+	let width = window_size.0 as f64;
+	let height = window_size.1 as f64;
+	let max_steps = (width.max(height) / DESIGN_GRID_SPACING as f64) as i64;
+	let on_screen = |pos: f64, size: f64| (0.0..=size).contains(&pos);
+
+	let mut lines = Vec::new();
+	for step in -max_steps..=max_steps {
+		let value = step * DESIGN_GRID_SPACING;
+		let x = origin.to_screen(window_size, (value as f64, 0.0)).0;
+		if !on_screen(x, width) {
+			continue;
+		}
+		let class = (value == 0).then_some("origin");
+		lines.push(html!(<>
+			<line {class} x1={x.to_string()} y1="0" x2={x.to_string()} y2={height.to_string()} />
+			<text x={(x + 2.0).to_string()} y="10">{value}</text>
+		</>));
+	}
+	for step in -max_steps..=max_steps {
+		let value = step * DESIGN_GRID_SPACING;
+		let y = origin.to_screen(window_size, (0.0, value as f64)).1;
+		if !on_screen(y, height) {
+			continue;
 		}
-		Ok(())
+		let class = (value == 0).then_some("origin");
+		lines.push(html!(<>
+			<line {class} x1="0" y1={y.to_string()} x2={width.to_string()} y2={y.to_string()} />
+			<text x="2" y={(y - 2.0).to_string()}>{value}</text>
+		</>));
 	}
+
+	html!(<svg class="design-grid">{lines}</svg>)
 }
 
 #[derive(Clone, PartialEq, Properties)]
 pub struct KeySwitchProps {
 	pub window_size: (u32, u32),
+	pub origin: Origin,
+	pub split: Split,
+	pub shape: SwitchShape,
 	pub switch_id: AttrValue,
 	pub switch: shared::Switch,
 	pub bindings: BoundSwitch,
 	pub active_slot: Option<SwitchSlot>,
+	pub tap_count: usize,
+	pub source_layer: AttrValue,
+	pub dimmed: bool,
+	// the switch is part of a combo which is active
+	pub combo_member_active: bool,
+	pub highlighted: bool,
+	// show a divider between the tap and hold bindings, when the switch has both
+	pub slot_divider: bool,
+	// the switch was just released, distinct from never having been pressed
+	pub released: bool,
+	#[prop_or_default]
+	pub onclick: Option<Callback<MouseEvent>>,
+	// shown while hovering the switch, when previewing the layout outside of the app
+	#[prop_or_default]
+	pub info: Option<SwitchInfo>,
 }
 
-fn calc_switch_pos(window_size: &(u32, u32), switch: &shared::Switch) -> (f64, f64) {
-	let mut pos = (switch.pos.0 as f64, switch.pos.1 as f64);
-	if switch.side == Some(shared::Side::Left) {
-		pos.0 *= -1f64;
-	}
-	calculate_screen_pos(window_size, pos, switch.size() as f64)
-}
-
-fn calculate_screen_pos(window_size: &(u32, u32), mut pos: (f64, f64), size: f64) -> (f64, f64) {
-	pos.0 = ((window_size.0 as f64) * 0.5) + pos.0 - (size * 0.5);
-	pos.1 = ((window_size.1 as f64) * 0.5) - pos.1 - (size * 0.5);
-	pos
+/// What the preview inspector shows about a switch while it is hovered, to help when authoring a layout.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwitchInfo {
+	// the position of the switch as authored in the layout, before any side mirroring or split
+	pub pos: (f32, f32),
+	// the bindings of each layer which binds this switch, in layer order
+	pub layer_bindings: Vec<(AttrValue, BoundSwitch)>,
 }
 
-static SWITCH_BORDER_WIDTH: u32 = 3;
-
 #[function_component]
 fn KeySwitch(
 	KeySwitchProps {
 		window_size,
+		origin,
+		split,
+		shape,
 		switch_id,
 		switch,
 		bindings,
 		active_slot,
+		tap_count,
+		source_layer,
+		dimmed,
+		combo_member_active,
+		highlighted,
+		slot_divider,
+		released,
+		onclick,
+		info,
 	}: &KeySwitchProps,
 ) -> Html {
+	let hovered = use_state_eq(|| false);
 	let mut class = classes!("switch");
-	let pos = calc_switch_pos(window_size, switch);
+	let pos = calc_switch_pos(window_size, *origin, *split, switch);
+	let outer_size = switch.size() + 2.0 * SWITCH_BORDER_WIDTH as f32;
 
 	let style = Style::from([
 		("--x", format!("{}px", pos.0)),
@@ -453,23 +1019,181 @@ fn KeySwitch(
 		("width", format!("{}px", switch.size())),
 		("height", format!("{}px", switch.size())),
 		("border-width", format!("{SWITCH_BORDER_WIDTH}px")),
+		("border-radius", format!("{}px", shape.border_radius(outer_size))),
+		("--rotation", format!("{}deg", split.rotation(switch.side))),
 	]);
 
-	if active_slot.is_some() {
+	if active_slot.is_some() || *combo_member_active {
 		class.push("active");
 	}
+	if *dimmed {
+		class.push("dimmed");
+	}
+	if bindings.is_blank() {
+		class.push("blank");
+	}
+	if *highlighted {
+		class.push("highlighted");
+	}
+	if *released {
+		class.push("released");
+	}
 
 	let mut contents = Vec::new();
+	if let Some(legend) = &switch.legend {
+		contents.push(html!(<div class="legend">{legend}</div>));
+	}
+	let has_tap = bindings.slots.contains_key(&SwitchSlot::Tap);
 	for (slot, binding) in &bindings.slots {
+		// Switches with both a tap and hold binding are divided between them, to show they have dual functions
+		if *slot == SwitchSlot::Hold && has_tap && *slot_divider {
+			contents.push(html!(<div class="slot-divider" />));
+		}
 		contents.push(html!(<SwitchSlotBinding slot={slot.clone()} binding={binding.clone()} />));
 	}
+	if *tap_count > 1 {
+		contents.push(html!(<div class="tap-count">{format!("x{tap_count}")}</div>));
+	}
+
+	// The inspector is only shown when previewing, where switches don't react to real input
+	let onmouseenter = info.is_some().then(|| {
+		let hovered = hovered.clone();
+		Callback::from(move |_: MouseEvent| hovered.set(true))
+	});
+	let onmouseleave = info.is_some().then(|| {
+		let hovered = hovered.clone();
+		Callback::from(move |_: MouseEvent| hovered.set(false))
+	});
+	if let Some(info) = info.as_ref().filter(|_| *hovered) {
+		contents.push(switch_info(switch_id, info));
+	}
 
 	let active_slot = active_slot.as_ref().map(SwitchSlot::to_string);
-	html!(<div id={switch_id.clone()} {class} {style} {active_slot}>
+	html!(<div
+		id={switch_id.clone()}
+		{class}
+		{style}
+		{active_slot}
+		{source_layer}
+		onclick={onclick.clone()}
+		{onmouseenter}
+		{onmouseleave}
+	>
 		{contents}
 	</div>)
 }
 
+/// The floating inspector box for a hovered switch, listing its id, position, and the bindings of every layer.
+fn switch_info(switch_id: &AttrValue, info: &SwitchInfo) -> Html {
+	let rows = info.layer_bindings.iter().map(|(layer_id, bindings)| {
+		let slots = bindings
+			.slots
+			.iter()
+			.map(|(slot, binding)| html!(<div class="info-slot">{format!("{slot}: {}", binding.input)}</div>));
+		html!(<div class="info-layer">
+			<div class="layer-name">{layer_id.clone()}</div>
+			{for slots}
+		</div>)
+	});
+	html!(<div class="switch-info">
+		<div class="info-id">{switch_id.clone()}</div>
+		<div class="info-pos">{format!("({}, {})", info.pos.0, info.pos.1)}</div>
+		{for rows}
+	</div>)
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct PlaceholderSwitchProps {
+	pub window_size: (u32, u32),
+	pub origin: Origin,
+	pub split: Split,
+	pub shape: SwitchShape,
+	pub switch_id: AttrValue,
+	pub switch: shared::Switch,
+}
+
+/// A faint outline of a switch which has no binding on any active layer, so the shape of the board stays visible.
+#[function_component]
+fn PlaceholderSwitch(
+	PlaceholderSwitchProps {
+		window_size,
+		origin,
+		split,
+		shape,
+		switch_id,
+		switch,
+	}: &PlaceholderSwitchProps,
+) -> Html {
+	let pos = calc_switch_pos(window_size, *origin, *split, switch);
+	let outer_size = switch.size() + 2.0 * SWITCH_BORDER_WIDTH as f32;
+	let style = Style::from([
+		("--x", format!("{}px", pos.0)),
+		("--y", format!("{}px", pos.1)),
+		("width", format!("{}px", switch.size())),
+		("height", format!("{}px", switch.size())),
+		("border-width", format!("{SWITCH_BORDER_WIDTH}px")),
+		("border-radius", format!("{}px", shape.border_radius(outer_size))),
+		("--rotation", format!("{}deg", split.rotation(switch.side))),
+	]);
+	let label = switch.legend.clone().unwrap_or_else(|| switch_id.to_string());
+	html!(<div id={switch_id.clone()} class={classes!("switch", "placeholder")} {style}>
+		<div class="legend">{label}</div>
+	</div>)
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct LayerStackSwitchProps {
+	pub window_size: (u32, u32),
+	pub origin: Origin,
+	pub split: Split,
+	pub shape: SwitchShape,
+	pub switch_id: AttrValue,
+	pub switch: shared::Switch,
+	// the bindings of each layer which binds this switch, in layer order
+	pub layer_bindings: Vec<(AttrValue, BoundSwitch)>,
+}
+
+/// A switch which lists the bindings from every layer, like a cheat sheet for authoring layouts.
+#[function_component]
+fn LayerStackSwitch(
+	LayerStackSwitchProps {
+		window_size,
+		origin,
+		split,
+		shape,
+		switch_id,
+		switch,
+		layer_bindings,
+	}: &LayerStackSwitchProps,
+) -> Html {
+	let pos = calc_switch_pos(window_size, *origin, *split, switch);
+	let outer_size = switch.size() + 2.0 * SWITCH_BORDER_WIDTH as f32;
+	let style = Style::from([
+		("--x", format!("{}px", pos.0)),
+		("--y", format!("{}px", pos.1)),
+		("width", format!("{}px", switch.size())),
+		("height", format!("{}px", switch.size())),
+		("border-width", format!("{SWITCH_BORDER_WIDTH}px")),
+		("border-radius", format!("{}px", shape.border_radius(outer_size))),
+		("--rotation", format!("{}deg", split.rotation(switch.side))),
+	]);
+
+	let rows = layer_bindings.iter().map(|(layer_id, bindings)| {
+		let slots = bindings.slots.iter().map(|(slot, binding)| {
+			let slot = slot.to_string();
+			html!(<div class="layer-slot" {slot}>{binding_element(binding)}</div>)
+		});
+		html!(<div class="layer-row" layer={layer_id.clone()}>
+			<div class="layer-name">{layer_id.clone()}</div>
+			{for slots}
+		</div>)
+	});
+
+	html!(<div id={switch_id.clone()} class={classes!("switch", "layer-stack")} {style}>
+		{for rows}
+	</div>)
+}
+
 #[derive(Clone, PartialEq, Properties)]
 pub struct SwitchSlotBindingProps {
 	slot: SwitchSlot,
@@ -482,15 +1206,21 @@ fn SwitchSlotBinding(SwitchSlotBindingProps { slot, binding }: &SwitchSlotBindin
 		SwitchSlot::Tap => class.push("center"),
 		SwitchSlot::Hold => class.push("bottom"),
 	}
-	let element = match &binding.display {
-		None => html!(<div class="label">{binding.input.to_string()}</div>),
-		Some(binding) => html!(<BindingDisplay binding={binding.clone()} />),
-	};
+	class.push(match binding.layer.is_some() {
+		true => "layer-key",
+		false => "action-key",
+	});
+	let element = binding_element(binding);
 
 	let layer = binding.layer.clone();
 	html!(<div {class} {layer}>{element}</div>)
 }
 
+/// The label of a binding, using its custom display if it has one.
+fn binding_element(binding: &Binding) -> Html {
+	html!(<BindingDisplay binding={binding.display_or_default()} />)
+}
+
 #[derive(Clone, PartialEq, Properties)]
 pub struct BindingDisplayProps {
 	binding: shared::BindingDisplay,
@@ -498,12 +1228,29 @@ pub struct BindingDisplayProps {
 #[function_component]
 fn BindingDisplay(BindingDisplayProps { binding }: &BindingDisplayProps) -> Html {
 	match &binding {
-		shared::BindingDisplay::Text(value) => html!(<div class="label">{value}</div>),
+		shared::BindingDisplay::Text(value) => {
+			// long labels are shrunk when the layout wraps labels, so they fit on two lines
+			let long = value.chars().count() > LONG_LABEL_CHARS;
+			html!(<div class={classes!("label", long.then_some("long"))}>{value}</div>)
+		}
 		shared::BindingDisplay::IconBootstrap(value) => html!(
 			<i class={format!("bi bi-{value}")} />
 		),
+		// Animated glyphs keep their own colors, so they are shown as-is instead of masking the icon color with them
+		shared::BindingDisplay::IconCustom(value) if is_animated_glyph(value) => html!(
+			<img class={classes!("icon", "animated")} src={format!("assets/glyph/{value}")} />
+		),
 		shared::BindingDisplay::IconCustom(value) => html!(
 			<img class={"icon"} style={format!("--glyph: url(assets/glyph/{value}.svg);")} />
 		),
 	}
 }
+
+// text labels longer than this many characters are considered long
+static LONG_LABEL_CHARS: usize = 3;
+
+/// Custom icons are svgs named without their extension, unless named with an animated image extension (e.g. "spinner.gif").
+fn is_animated_glyph(name: &str) -> bool {
+	static ANIMATED_EXTENSIONS: [&str; 2] = [".gif", ".apng"];
+	ANIMATED_EXTENSIONS.iter().any(|extension| name.ends_with(extension))
+}
@@ -0,0 +1,24 @@
+//! Parses arbitrary kdl documents as layouts, which must fail with an error instead of panicking.
+//! Layouts which do parse are saved again, since the config is written back whenever it changes.
+//!
+//! Run from `crates/shared` with `cargo +nightly fuzz run parse_layout`.
+//! Seeding the corpus with a real config (e.g. copying the repo's `config.kdl` into `fuzz/corpus/parse_layout`)
+//! gets the fuzzer past the kdl syntax and into the layout much sooner.
+#![no_main]
+
+use kdlize::AsKdl;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+	let Ok(doc) = data.parse::<kdl::KdlDocument>() else {
+		return;
+	};
+	// the layout is usually nested in a config, but any node can be read as one
+	for node in doc.nodes() {
+		let mut reader = kdlize::NodeReader::new_root(node, ());
+		let Ok(layout) = shared::Layout::parse(&mut reader) else {
+			continue;
+		};
+		let _ = layout.as_kdl().build("layout").to_string();
+	}
+});
@@ -1,4 +1,4 @@
-use crate::{KeySet, SwitchSlot};
+use crate::{KeySet, Switch, SwitchSlot};
 use kdlize::{ext::ValueExt, AsKdl, FromKdl};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -6,25 +6,77 @@ use std::collections::BTreeMap;
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct BoundSwitch {
 	pub slots: BTreeMap<SwitchSlot, Binding>,
+	// where the switch is drawn while this layer supplies its binding, overriding the switch's own position and size
+	pub x: Option<f32>,
+	pub y: Option<f32>,
+	pub size: Option<f32>,
+	// other layers this binding also applies to, for keys shared by several layers (but not the base layer)
+	pub also_layers: Vec<String>,
+	// the layer this binding was copied from by its `also`, if it isn't authored on its own layer.
+	// Copies are recreated whenever the layout is parsed, so they are never written back to kdl.
+	#[serde(default)]
+	pub shared_from: Option<String>,
+}
+
+impl BoundSwitch {
+	/// Returns true if the switch is explicitly bound to nothing, blocking any bindings from lower layers.
+	pub fn is_blank(&self) -> bool {
+		self.slots.is_empty()
+	}
+
+	/// The switch as drawn while this binding is active, with any position or size overrides applied.
+	pub fn override_switch(&self, switch: &Switch) -> Switch {
+		Switch {
+			pos: (self.x.unwrap_or(switch.pos.0), self.y.unwrap_or(switch.pos.1)),
+			size: self.size.or(switch.size),
+			..switch.clone()
+		}
+	}
 }
 
 impl FromKdl<()> for BoundSwitch {
 	type Error = anyhow::Error;
 
 	fn from_kdl<'doc>(node: &mut kdlize::NodeReader<'doc, ()>) -> Result<Self, Self::Error> {
+		let x = node.get_f64_opt("x")?.map(|x| x as f32);
+		let y = node.get_f64_opt("y")?.map(|y| y as f32);
+		let size = node.get_f64_opt("size")?.map(|size| size as f32);
+		let mut also_layers = Vec::new();
+		for mut node in node.query_all("scope() > also")? {
+			while let Some(entry) = node.next_opt() {
+				also_layers.push(entry.as_str_req()?.to_owned());
+			}
+		}
 		let mut slots = BTreeMap::new();
 		for mut node in node.query_all("scope() > slot")? {
 			let slot = node.next_str_req_t::<SwitchSlot>()?;
 			let binding = Binding::from_kdl(&mut node)?;
 			slots.insert(slot, binding);
 		}
-		Ok(Self { slots })
+		Ok(Self {
+			slots,
+			x,
+			y,
+			size,
+			also_layers,
+			shared_from: None,
+		})
 	}
 }
 
 impl AsKdl for BoundSwitch {
 	fn as_kdl(&self) -> kdlize::NodeBuilder {
 		let mut node = kdlize::NodeBuilder::default();
+		node.entry(("x", self.x.map(|x| x as f64)));
+		node.entry(("y", self.y.map(|y| y as f64)));
+		node.entry(("size", self.size.map(|size| size as f64)));
+		if !self.also_layers.is_empty() {
+			let mut also = kdlize::NodeBuilder::default();
+			for layer in &self.also_layers {
+				also.entry(layer.as_str());
+			}
+			node.child(also.build("also"));
+		}
 		for (slot, binding) in &self.slots {
 			node.child(
 				kdlize::NodeBuilder::default()
@@ -37,11 +89,56 @@ impl AsKdl for BoundSwitch {
 	}
 }
 
+/// The priority of bindings which activate a layer, unless they set their own `priority`.
+/// Bindings triggered by the same key event are emitted highest priority first, so by default a layer is
+/// activated before any switch presses bound to the same key are applied.
+pub const LAYER_BINDING_PRIORITY: i64 = 1;
+
+/// The priority of a binding which doesn't set one, based on whether it activates a layer.
+pub fn default_binding_priority(activates_layer: bool) -> i64 {
+	match activates_layer {
+		true => LAYER_BINDING_PRIORITY,
+		false => 0,
+	}
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Binding {
 	pub input: KeySet,
 	pub display: Option<BindingDisplay>,
+	// the display while shift is held, like the upper legend of a keycap
+	pub shifted: Option<BindingDisplay>,
 	pub layer: Option<String>,
+	// the key actually sent by the keyboard, for keyboards which remap keys before they reach the os
+	pub physical: Option<KeySet>,
+	// the name of an effect emitted whenever this binding is pressed, for the frontend or plugins to react to
+	pub on_press: Option<String>,
+	// the order this binding is emitted in, relative to other bindings triggered by the same key event (highest first)
+	pub priority: Option<i64>,
+	// the name of the keyboard this binding is limited to, for telling a macropad apart from the main keyboard
+	pub device: Option<String>,
+}
+
+impl Binding {
+	/// The keys which are detected to trigger this binding.
+	/// This is the `physical` key when provided, otherwise the displayed `input`.
+	pub fn detected_input(&self) -> &KeySet {
+		self.physical.as_ref().unwrap_or(&self.input)
+	}
+
+	/// The display of this binding, derived from its input keys if no display was provided.
+	pub fn display_or_default(&self) -> BindingDisplay {
+		match &self.display {
+			Some(display) => display.clone(),
+			None => BindingDisplay::from_keys(&self.input),
+		}
+	}
+
+	/// The priority of this binding, defaulting to `LAYER_BINDING_PRIORITY` for bindings which activate a layer.
+	pub fn priority(&self) -> i64 {
+		let default_priority = default_binding_priority(self.layer.is_some());
+		self.priority.unwrap_or(default_priority)
+	}
 }
 
 impl FromKdl<()> for Binding {
@@ -54,7 +151,21 @@ impl FromKdl<()> for Binding {
 			Some(entry) => Some(BindingDisplay::try_from(entry)?),
 		};
 		let layer = node.get_str_opt("layer")?.map(str::to_owned);
-		Ok(Self { input, display, layer })
+		let physical = node.get_str_opt_t::<KeySet>("physical")?;
+		let on_press = node.get_str_opt("on_press")?.map(str::to_owned);
+		let priority = node.get_i64_opt("priority")?;
+		let device = node.get_str_opt("device")?.map(str::to_owned);
+		let shifted = node.query_opt_t("scope() > shifted")?;
+		Ok(Self {
+			input,
+			display,
+			shifted,
+			layer,
+			physical,
+			on_press,
+			priority,
+			device,
+		})
 	}
 }
 
@@ -70,6 +181,13 @@ impl AsKdl for Binding {
 			}
 		}
 		node.entry(("layer", self.layer.clone()));
+		node.entry(("physical", self.physical.as_ref().map(KeySet::to_string)));
+		node.entry(("on_press", self.on_press.clone()));
+		node.entry(("priority", self.priority));
+		node.entry(("device", self.device.clone()));
+		if let Some(shifted) = &self.shifted {
+			node.child(("shifted", shifted));
+		}
 		node
 	}
 }
@@ -81,10 +199,21 @@ pub enum BindingDisplay {
 	IconCustom(String),
 }
 
+impl BindingDisplay {
+	/// The default text display for a set of keys, using their keycap legends.
+	pub fn from_keys(keys: &KeySet) -> Self {
+		Self::Text(keys.legend())
+	}
+}
+
 #[derive(thiserror::Error, Debug)]
 #[error("Invalid binding display type {0}, expecting IconBootstrap or IconCustom")]
 pub struct InvalidBindingDisplay(String);
 
+#[derive(thiserror::Error, Debug)]
+#[error("Missing binding display value")]
+pub struct MissingBindingDisplay;
+
 impl TryFrom<&kdl::KdlEntry> for BindingDisplay {
 	type Error = anyhow::Error;
 
@@ -101,6 +230,17 @@ impl TryFrom<&kdl::KdlEntry> for BindingDisplay {
 	}
 }
 
+impl FromKdl<()> for BindingDisplay {
+	type Error = anyhow::Error;
+
+	fn from_kdl<'doc>(node: &mut kdlize::NodeReader<'doc, ()>) -> Result<Self, Self::Error> {
+		let Some(entry) = node.next_opt() else {
+			return Err(MissingBindingDisplay)?;
+		};
+		Self::try_from(entry)
+	}
+}
+
 impl AsKdl for BindingDisplay {
 	fn as_kdl(&self) -> kdlize::NodeBuilder {
 		let mut node = kdlize::NodeBuilder::default();
@@ -112,3 +252,65 @@ impl AsKdl for BindingDisplay {
 		node
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::layout::test::parse_layout;
+
+	#[test]
+	fn shifted_display() {
+		let layout = parse_layout(
+			r#"
+layout {
+	default_layer "base"
+	switch "l_index" 0 0
+	switch "r_index" 50 0
+	layer "base" {
+		bind "l_index" {
+			slot "Tap" "F" "f" {
+				shifted "F"
+			}
+		}
+		bind "r_index" {
+			slot "Tap" "1" {
+				shifted (IconBootstrap)"exclamation"
+			}
+		}
+	}
+}
+"#,
+		);
+		let layer = layout.get_layer("base").expect("missing base layer");
+		let shifted = |switch_id: &str| {
+			let bindings = layer.get_binding(switch_id).expect("missing binding");
+			bindings.slots[&SwitchSlot::Tap].shifted.clone()
+		};
+		assert_eq!(shifted("l_index"), Some(BindingDisplay::Text("F".into())));
+		assert_eq!(
+			shifted("r_index"),
+			Some(BindingDisplay::IconBootstrap("exclamation".into()))
+		);
+	}
+
+	#[test]
+	fn binding_device() {
+		let layout = parse_layout(
+			r#"
+layout {
+	default_layer "base"
+	switch "macro_1" 0 0
+	layer "base" {
+		bind "macro_1" {
+			slot "Tap" "F13" "Mute" device="macropad"
+		}
+	}
+}
+"#,
+		);
+		let layer = layout.get_layer("base").expect("missing base layer");
+		let bindings = layer.get_binding("macro_1").expect("missing binding");
+		let device = bindings.slots[&SwitchSlot::Tap].device.clone();
+		assert_eq!(device, Some("macropad".into()));
+	}
+}
@@ -1,19 +1,29 @@
-use crate::{BindingDisplay, KeySet};
+use crate::{geometry::COMBO_SIZE, BindingDisplay, KeySet, Side, SwitchShape};
 use kdlize::{
 	ext::{EntryExt, ValueExt},
 	AsKdl, FromKdl, OmitIfEmpty,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Combo {
 	pub id: String,
 	pub layers: Vec<String>,
 	pub pos: (f32, f32),
+	// combos on the left side have their x position negated, the same as switches
+	pub side: Option<Side>,
 	pub label: BindingDisplay,
+	// labels to show instead of `label` when a specific layer is the highest priority active layer
+	pub layer_labels: BTreeMap<String, BindingDisplay>,
 	pub links: Vec<Link>,
 	pub input: KeySet,
 	pub input_layer: Option<String>,
+	// milliseconds the input must be held before the combo activates, releasing sooner cancels it
+	pub hold_ms: Option<u64>,
+	// the width and height of the combo in pixels, and its outline
+	pub size: Option<f32>,
+	pub shape: Option<SwitchShape>,
 }
 
 impl FromKdl<()> for Combo {
@@ -24,6 +34,9 @@ impl FromKdl<()> for Combo {
 		let pos_x = node.next_f64_req()? as f32;
 		let pos_y = node.next_f64_req()? as f32;
 		let label = BindingDisplay::try_from(node.next_req()?)?;
+		let side = node.get_str_opt_t::<Side>("side")?;
+		let size = node.get_f64_opt("size")?.map(|size| size as f32);
+		let shape = node.get_str_opt_t::<SwitchShape>("shape")?;
 
 		let mut layers = Vec::new();
 		for mut node in node.query_all("scope() > layers")? {
@@ -32,6 +45,13 @@ impl FromKdl<()> for Combo {
 			}
 		}
 
+		let mut layer_labels = BTreeMap::new();
+		for mut node in node.query_all("scope() > label")? {
+			let layer = node.next_str_req()?.to_owned();
+			let label = BindingDisplay::try_from(node.next_req()?)?;
+			layer_labels.insert(layer, label);
+		}
+
 		let links = node.query_all_t("scope() > link")?;
 
 		let (input, input_layer) = {
@@ -40,19 +60,57 @@ impl FromKdl<()> for Combo {
 			let layer = node.get_str_opt("layer")?.map(str::to_owned);
 			(input, layer)
 		};
+		let hold_ms = node.query_i64_opt("scope() > hold_ms", 0)?.map(|ms| ms as u64);
 
 		Ok(Self {
 			id,
 			layers,
 			pos: (pos_x, pos_y),
+			side,
 			label,
+			layer_labels,
 			links,
 			input,
 			input_layer,
+			hold_ms,
+			size,
+			shape,
 		})
 	}
 }
 
+impl Combo {
+	/// The width and height of the combo in pixels.
+	pub fn size(&self) -> f64 {
+		self.size.map_or(COMBO_SIZE, |size| size as f64)
+	}
+
+	/// The outline of the combo, which is rounded unless specified.
+	pub fn shape(&self) -> SwitchShape {
+		self.shape.unwrap_or_default()
+	}
+
+	/// The switches which make up this combo, as referenced by its links.
+	pub fn member_switches(&self) -> impl Iterator<Item = &String> + '_ {
+		let points = self.links.iter().flat_map(|link| link.points().iter());
+		points.filter_map(|point| match point {
+			LinkPoint::Switch(switch_id, _, _) => Some(switch_id),
+			_ => None,
+		})
+	}
+
+	/// The label to display, given the priority order of layers and which of them are active.
+	pub fn active_label<'a>(
+		&'a self,
+		layer_order: &'a [String],
+		is_active: impl Fn(&String) -> bool,
+	) -> &'a BindingDisplay {
+		let active_layers = layer_order.iter().rev().filter(|layer| is_active(*layer));
+		let mut layer_labels = active_layers.filter_map(|layer| self.layer_labels.get(layer));
+		layer_labels.next().unwrap_or(&self.label)
+	}
+}
+
 impl AsKdl for Combo {
 	fn as_kdl(&self) -> kdlize::NodeBuilder {
 		let mut node = kdlize::NodeBuilder::default();
@@ -60,6 +118,9 @@ impl AsKdl for Combo {
 		node.entry(self.pos.0 as f64);
 		node.entry(self.pos.1 as f64);
 		node += self.label.as_kdl();
+		node.entry(("side", self.side.as_ref().map(Side::to_string)));
+		node.entry(("size", self.size.map(|size| size as f64)));
+		node.entry(("shape", self.shape.as_ref().map(SwitchShape::to_string)));
 		node.child((
 			{
 				let mut node = kdlize::NodeBuilder::default();
@@ -70,6 +131,14 @@ impl AsKdl for Combo {
 			},
 			OmitIfEmpty,
 		));
+		for (layer, label) in &self.layer_labels {
+			node.child(
+				kdlize::NodeBuilder::default()
+					.with_entry(layer.as_str())
+					.with(label.as_kdl())
+					.build("label"),
+			);
+		}
 		node.children(("link", &self.links));
 		node.child(("bind", {
 			let mut node = kdlize::NodeBuilder::default();
@@ -77,16 +146,39 @@ impl AsKdl for Combo {
 			node.entry(("layer", self.input_layer.clone()));
 			node
 		}));
+		if let Some(hold_ms) = &self.hold_ms {
+			node.child(("hold_ms", &(*hold_ms as i64)));
+		}
 		node
 	}
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub struct Link(Vec<LinkPoint>);
+pub struct Link {
+	points: Vec<LinkPoint>,
+	// links on the left side are mirrored horizontally, so a link authored for the right half can be reused for the left
+	side: Option<Side>,
+	// draws an arrowhead at the end of the link, to show which way it points
+	arrow: bool,
+	// an svg `stroke-dasharray` (e.g. "4 2"), to draw the link dashed
+	dash: Option<String>,
+}
 
 impl Link {
 	pub fn points(&self) -> &Vec<LinkPoint> {
-		&self.0
+		&self.points
+	}
+
+	pub fn side(&self) -> Option<Side> {
+		self.side
+	}
+
+	pub fn arrow(&self) -> bool {
+		self.arrow
+	}
+
+	pub fn dash(&self) -> Option<&String> {
+		self.dash.as_ref()
 	}
 }
 
@@ -94,19 +186,32 @@ impl FromKdl<()> for Link {
 	type Error = anyhow::Error;
 
 	fn from_kdl<'doc>(node: &mut kdlize::NodeReader<'doc, ()>) -> Result<Self, Self::Error> {
+		let side = node.get_str_opt_t::<Side>("side")?;
+		let arrow = node.get_bool_opt("arrow")?.unwrap_or_default();
+		let dash = node.get_str_opt("dash")?.map(str::to_owned);
 		let children = node.children().unwrap_or_default();
 		let mut points = Vec::with_capacity(children.len());
 		for mut node in children {
 			points.push(LinkPoint::from_kdl(&mut node)?);
 		}
-		Ok(Self(points))
+		Ok(Self {
+			points,
+			side,
+			arrow,
+			dash,
+		})
 	}
 }
 
 impl AsKdl for Link {
 	fn as_kdl(&self) -> kdlize::NodeBuilder {
 		let mut node = kdlize::NodeBuilder::default();
-		for point in &self.0 {
+		node.entry(("side", self.side.as_ref().map(Side::to_string)));
+		if self.arrow {
+			node.entry(("arrow", true));
+		}
+		node.entry(("dash", self.dash.clone()));
+		for point in &self.points {
 			node.child((point.node_id(), point.as_kdl()));
 		}
 		node
@@ -236,3 +341,67 @@ impl AsKdl for LinkPoint {
 		node
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{geometry::calc_combo_pos, layout::test::parse_layout};
+
+	#[test]
+	fn combo_size_and_shape() {
+		let layout = parse_layout(
+			r#"
+layout {
+	default_layer "base"
+	combo "esc" 0 25 "Esc" size=40 shape="square" {
+		bind "Escape"
+	}
+	combo "tab" 0 -25 "Tab" {
+		bind "Tab"
+	}
+}
+"#,
+		);
+		let [esc, tab] = &layout.combos()[..] else {
+			panic!("expected two combos");
+		};
+		assert_eq!((esc.size(), esc.shape()), (40.0, SwitchShape::Square));
+		assert_eq!(tab.size(), COMBO_SIZE);
+		assert_eq!(tab.shape(), SwitchShape::Rounded);
+		// combos are positioned by their center, so larger combos extend further
+		let pos = calc_combo_pos(&(200, 200), Default::default(), Default::default(), esc);
+		assert_eq!(pos, (80.0, 55.0));
+	}
+
+	#[test]
+	fn link_arrow_and_dash() {
+		let layout = parse_layout(
+			r#"
+layout {
+	default_layer "base"
+	switch "l_index" 0 0
+	switch "r_index" 50 0
+	combo "esc" 25 25 "Esc" {
+		layers "base"
+		link arrow=true dash="4 2" {
+			switch "l_index" 0 1
+			anchor 0 -1
+		}
+		link {
+			switch "r_index" 0 1
+			anchor 0 -1
+		}
+		bind "Escape"
+	}
+	layer "base"
+}
+"#,
+		);
+		let combo = layout.combos().first().expect("missing combo");
+		let styles = combo.links.iter().map(|link| (link.arrow(), link.dash().cloned()));
+		assert_eq!(
+			styles.collect::<Vec<_>>(),
+			vec![(true, Some("4 2".into())), (false, None)]
+		);
+	}
+}
@@ -0,0 +1,53 @@
+use crate::KeySet;
+use kdlize::{AsKdl, FromKdl};
+use serde::{Deserialize, Serialize};
+
+/// A key which types nothing on its own, but composes a character with the next key pressed,
+/// such as `´` followed by `e` typing `é` on many european layouts.
+///
+/// Declared in the layout as `dead_key "Quote" { compose "E" "é"; }`, with a `compose` for each key that can follow it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DeadKey {
+	pub key: KeySet,
+	// the keys which can follow the dead key, and the glyph that sequence types
+	pub compositions: Vec<(KeySet, String)>,
+}
+
+impl DeadKey {
+	/// The glyph typed by pressing `keys` after this dead key, if that sequence composes a character.
+	pub fn compose(&self, keys: &KeySet) -> Option<&String> {
+		let mut compositions = self.compositions.iter();
+		compositions.find(|(next, _)| next == keys).map(|(_, glyph)| glyph)
+	}
+}
+
+impl FromKdl<()> for DeadKey {
+	type Error = anyhow::Error;
+
+	fn from_kdl<'doc>(node: &mut kdlize::NodeReader<'doc, ()>) -> Result<Self, Self::Error> {
+		let key = node.next_str_req_t::<KeySet>()?;
+		let mut compositions = Vec::new();
+		for mut node in node.query_all("scope() > compose")? {
+			let next = node.next_str_req_t::<KeySet>()?;
+			let glyph = node.next_str_req()?.to_owned();
+			compositions.push((next, glyph));
+		}
+		Ok(Self { key, compositions })
+	}
+}
+
+impl AsKdl for DeadKey {
+	fn as_kdl(&self) -> kdlize::NodeBuilder {
+		let mut node = kdlize::NodeBuilder::default();
+		node.entry(self.key.to_string());
+		for (next, glyph) in &self.compositions {
+			node.child(
+				kdlize::NodeBuilder::default()
+					.with_entry(next.to_string())
+					.with_entry(glyph.as_str())
+					.build("compose"),
+			);
+		}
+		node
+	}
+}
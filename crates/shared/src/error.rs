@@ -0,0 +1,67 @@
+use crate::{
+	geometry::{InvalidOrigin, InvalidUnits},
+	InvalidBindingDisplay, InvalidKeyAlias, InvalidLinkPointAxis, InvalidLinkPointDirection, InvalidLinkPointType,
+	InvalidSide, InvalidSlot, InvalidSwitchShape,
+};
+
+/// A failure to parse a layout, separated by what was invalid so tooling can handle specific failures.
+///
+/// The `FromKdl` implementations use `anyhow` internally (so they can mix in errors from kdlize's readers),
+/// this recovers the specific error types from those errors.
+#[derive(thiserror::Error, Debug)]
+pub enum LayoutParseError {
+	#[error(transparent)]
+	KeyAlias(#[from] InvalidKeyAlias),
+	#[error(transparent)]
+	BindingDisplay(#[from] InvalidBindingDisplay),
+	#[error(transparent)]
+	Slot(#[from] InvalidSlot),
+	#[error(transparent)]
+	Side(#[from] InvalidSide),
+	#[error(transparent)]
+	SwitchShape(#[from] InvalidSwitchShape),
+	#[error(transparent)]
+	Origin(#[from] InvalidOrigin),
+	#[error(transparent)]
+	Units(#[from] InvalidUnits),
+	#[error(transparent)]
+	LinkPointType(#[from] InvalidLinkPointType),
+	#[error(transparent)]
+	LinkPointDirection(#[from] InvalidLinkPointDirection),
+	#[error(transparent)]
+	LinkPointAxis(#[from] InvalidLinkPointAxis),
+	/// The document is malformed (e.g. a missing node or entry, or an entry of the wrong type).
+	#[error(transparent)]
+	Kdl(anyhow::Error),
+}
+
+impl From<anyhow::Error> for LayoutParseError {
+	fn from(err: anyhow::Error) -> Self {
+		macro_rules! downcast_into {
+			($err:expr, $($ty:ty),+) => {{
+				let err = $err;
+				$(
+					let err = match err.downcast::<$ty>() {
+						Ok(specific) => return Self::from(specific),
+						Err(err) => err,
+					};
+				)+
+				err
+			}};
+		}
+		let err = downcast_into!(
+			err,
+			InvalidKeyAlias,
+			InvalidBindingDisplay,
+			InvalidSlot,
+			InvalidSide,
+			InvalidSwitchShape,
+			InvalidOrigin,
+			InvalidUnits,
+			InvalidLinkPointType,
+			InvalidLinkPointDirection,
+			InvalidLinkPointAxis
+		);
+		Self::Kdl(err)
+	}
+}
@@ -0,0 +1,465 @@
+use crate::{Combo, Layout, Link, LinkPoint, Side, Switch};
+use serde::{Deserialize, Serialize};
+
+pub static SWITCH_BORDER_WIDTH: u32 = 3;
+pub static COMBO_SIZE: f64 = 30f64;
+
+/// The coordinate convention used for positions in a layout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Origin {
+	/// Positions are relative to the center of the window, with +y being up.
+	#[default]
+	Center,
+	/// Positions are relative to the top-left of the window, with +y being down.
+	TopLeft,
+}
+
+impl Origin {
+	/// Converts a point in layout coordinates into a point in screen coordinates.
+	pub fn to_screen(self, window_size: &(u32, u32), pos: (f64, f64)) -> (f64, f64) {
+		match self {
+			Self::Center => (
+				(window_size.0 as f64 * 0.5) + pos.0,
+				(window_size.1 as f64 * 0.5) - pos.1,
+			),
+			Self::TopLeft => pos,
+		}
+	}
+
+	/// Mirrors a point in layout coordinates across the vertical center line of the window.
+	pub fn mirror_x(self, window_size: &(u32, u32), pos: (f64, f64)) -> (f64, f64) {
+		match self {
+			Self::Center => (-pos.0, pos.1),
+			Self::TopLeft => (window_size.0 as f64 - pos.0, pos.1),
+		}
+	}
+}
+
+impl std::fmt::Display for Origin {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Self::Center => "center",
+				Self::TopLeft => "top-left",
+			}
+		)
+	}
+}
+
+impl std::str::FromStr for Origin {
+	type Err = InvalidOrigin;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"center" => Ok(Self::Center),
+			"top-left" => Ok(Self::TopLeft),
+			_ => Err(InvalidOrigin(s.to_owned())),
+		}
+	}
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid Origin {0}, expecting \"center\" or \"top-left\"")]
+pub struct InvalidOrigin(String);
+
+/// The pixels in one key unit (1u, the distance between neighboring keys), when the layout doesn't specify `unit_px`.
+pub static DEFAULT_UNIT_PX: f32 = 54.0;
+/// The space left between neighboring keycaps in a layout authored in key units, so switches 1u apart don't touch.
+pub static KEY_UNIT_GAP_PX: f32 = 9.0;
+/// The distance between each switch in a stack, along both axes, so the stacked switches are all visible.
+pub static STACK_OFFSET_PX: f32 = 8.0;
+
+/// The unit which switch positions and sizes are authored in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Units {
+	/// Pixels, as rendered at a scale of 1.
+	#[default]
+	Px,
+	/// Key units, where 1u is the distance between neighboring keys (19.05mm on a physical keyboard).
+	U,
+}
+
+impl std::fmt::Display for Units {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Self::Px => "px",
+				Self::U => "u",
+			}
+		)
+	}
+}
+
+impl std::str::FromStr for Units {
+	type Err = InvalidUnits;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"px" => Ok(Self::Px),
+			"u" => Ok(Self::U),
+			_ => Err(InvalidUnits(s.to_owned())),
+		}
+	}
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid Units {0}, expecting \"px\" or \"u\"")]
+pub struct InvalidUnits(String);
+
+/// Converts a layout position (the center of an element, in the coordinates of `origin`)
+/// into the top-left screen position of an element of the provided size.
+pub fn calculate_screen_pos(window_size: &(u32, u32), origin: Origin, pos: (f64, f64), size: f64) -> (f64, f64) {
+	let mut pos = origin.to_screen(window_size, pos);
+	pos.0 -= size * 0.5;
+	pos.1 -= size * 0.5;
+	pos
+}
+
+/// The separation between the halves of a split keyboard, applied to everything which is on a side.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Split {
+	/// Pixels added between the halves, moving each half away from the center of the window.
+	pub gap: f64,
+	/// Degrees each half is rotated about the center of the window,
+	/// clockwise for the right half and counter-clockwise for the left (lowering the outer ends of both halves).
+	pub angle: f64,
+}
+
+impl Split {
+	/// The degrees (clockwise on screen) that elements on a side are rotated by.
+	pub fn rotation(&self, side: Option<Side>) -> f64 {
+		match side {
+			None => 0.0,
+			Some(Side::Left) => -self.angle,
+			Some(Side::Right) => self.angle,
+		}
+	}
+
+	/// Rotates a screen-space offset by the rotation of a side.
+	pub fn rotate(&self, side: Option<Side>, offset: (f64, f64)) -> (f64, f64) {
+		let (sin, cos) = self.rotation(side).to_radians().sin_cos();
+		// screen y is down, so this is a clockwise rotation on screen
+		(offset.0 * cos - offset.1 * sin, offset.0 * sin + offset.1 * cos)
+	}
+
+	/// Moves a screen position on a side of the keyboard to where it is on that split half.
+	pub fn apply(&self, window_size: &(u32, u32), side: Option<Side>, pos: (f64, f64)) -> (f64, f64) {
+		let direction = match side {
+			None => return pos,
+			Some(Side::Left) => -1.0,
+			Some(Side::Right) => 1.0,
+		};
+		let center = (window_size.0 as f64 * 0.5, window_size.1 as f64 * 0.5);
+		let offset = self.rotate(side, (pos.0 - center.0, pos.1 - center.1));
+		(center.0 + offset.0 + self.gap * 0.5 * direction, center.1 + offset.1)
+	}
+}
+
+/// The top-left screen position of a switch, which is centered on its (possibly mirrored and split) position.
+pub fn calc_switch_pos(window_size: &(u32, u32), origin: Origin, split: Split, switch: &Switch) -> (f64, f64) {
+	let mut pos = (switch.pos.0 as f64, switch.pos.1 as f64);
+	if switch.side == Some(Side::Left) {
+		pos = origin.mirror_x(window_size, pos);
+	}
+	let center = split.apply(window_size, switch.side, origin.to_screen(window_size, pos));
+	let half_size = switch.size() as f64 * 0.5;
+	(center.0 - half_size, center.1 - half_size)
+}
+
+/// The top-left screen position of a combo, which is centered on its (possibly mirrored and split) position.
+pub fn calc_combo_pos(window_size: &(u32, u32), origin: Origin, split: Split, combo: &Combo) -> (f64, f64) {
+	let mut pos = (combo.pos.0 as f64, combo.pos.1 as f64);
+	if combo.side == Some(Side::Left) {
+		pos = origin.mirror_x(window_size, pos);
+	}
+	let center = split.apply(window_size, combo.side, origin.to_screen(window_size, pos));
+	let size = combo.size();
+	(center.0 - size * 0.5, center.1 - size * 0.5)
+}
+
+/// The screen-space bounds containing every switch in the layout (including their borders),
+/// or None if the layout has no switches.
+pub fn switch_bounds(layout: &Layout, window_size: &(u32, u32)) -> Option<Bounds> {
+	let border = SWITCH_BORDER_WIDTH as f64;
+	let mut bounds = None::<Bounds>;
+	for (switch_id, switch) in layout.switches() {
+		let switch = layout.switch_placement(switch_id, switch);
+		let pos = calc_switch_pos(window_size, layout.origin(), layout.split(), &switch);
+		let size = switch.size() as f64 + border * 2.0;
+		let switch_bounds = Bounds {
+			min: pos,
+			max: (pos.0 + size, pos.1 + size),
+		};
+		bounds = Some(bounds.map_or(switch_bounds, |bounds| bounds.union(switch_bounds)));
+	}
+	bounds
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("failed to draw link for combo {combo_id}, invalid switch id {switch_id}")]
+pub struct InvalidLinkSwitch {
+	pub combo_id: String,
+	pub switch_id: String,
+}
+
+/// Builds the screen-space path for a link between a combo and the switches/points it references.
+pub fn build_link_path(
+	layout: &Layout,
+	window_size: &(u32, u32),
+	combo: &Combo,
+	link: &Link,
+) -> Result<ComboLinkPath, InvalidLinkSwitch> {
+	let origin = layout.origin();
+	let split = layout.split();
+	let combo_pos = calc_combo_pos(window_size, origin, split, combo);
+	// Left side links are mirrored, so relative offsets and control directions flip on the x axis
+	let mirrored = link.side() == Some(Side::Left);
+	let x_scale = if mirrored { -1f64 } else { 1f64 };
+	let mut path = ComboLinkPath::default();
+	for point in link.points() {
+		match point {
+			LinkPoint::Switch(switch_id, rel_x, rel_y) => {
+				let rel_x = rel_x * x_scale;
+				let Some(switch) = layout.switches().get(switch_id) else {
+					return Err(InvalidLinkSwitch {
+						combo_id: combo.id.clone(),
+						switch_id: switch_id.clone(),
+					});
+				};
+				let switch = &layout.switch_placement(switch_id, switch);
+				let half_size = switch.size() as f64 * 0.5 + SWITCH_BORDER_WIDTH as f64;
+				// get the top-left pos
+				let mut pos = calc_switch_pos(window_size, origin, split, switch);
+				// center the coords
+				pos.0 += half_size;
+				pos.1 += half_size;
+				// apply relative offset, turned with the switch when it's on a split half
+				let offset = split.rotate(switch.side, (rel_x * half_size, rel_y * half_size));
+				pos.0 += offset.0;
+				pos.1 += offset.1;
+				path.push(pos);
+			}
+			LinkPoint::Point {
+				pos,
+				control_dirs,
+				control_incoming_axis,
+				control_size,
+			} => {
+				let pos = match mirrored {
+					true => origin.mirror_x(window_size, *pos),
+					false => *pos,
+				};
+				let control_dirs = (control_dirs.0 * x_scale, control_dirs.1);
+				let control = origin.to_screen(window_size, pos);
+				let mut a = control;
+				let mut b = control;
+				if *control_incoming_axis == 0 {
+					a.0 += *control_size * control_dirs.0;
+					b.1 += *control_size * control_dirs.1;
+				} else {
+					a.1 += *control_size * control_dirs.1;
+					b.0 += *control_size * control_dirs.0;
+				}
+				// points move with the half of the keyboard the link is on
+				let [a, control, b] = [a, control, b].map(|pos| split.apply(window_size, link.side(), pos));
+				path.push_curve(a, control, b);
+			}
+			LinkPoint::Anchor(rel_x, rel_y) => {
+				let rel_x = rel_x * x_scale;
+				let half_size = combo.size() * 0.5 + (SWITCH_BORDER_WIDTH as f64);
+				let mut pos = combo_pos;
+				// center the coords
+				pos.0 += half_size;
+				pos.1 += half_size;
+				// apply relative offset, turned with the combo when it's on a split half
+				let offset = split.rotate(combo.side, (rel_x * half_size, rel_y * half_size));
+				pos.0 += offset.0;
+				pos.1 += offset.1;
+				path.push(pos);
+			}
+		};
+	}
+	Ok(path)
+}
+
+fn segment_abs(segment: &svgtypes::PathSegment) -> bool {
+	use svgtypes::PathSegment::*;
+	match segment {
+		MoveTo { abs, .. } => *abs,
+		LineTo { abs, .. } => *abs,
+		HorizontalLineTo { abs, .. } => *abs,
+		VerticalLineTo { abs, .. } => *abs,
+		CurveTo { abs, .. } => *abs,
+		SmoothCurveTo { abs, .. } => *abs,
+		Quadratic { abs, .. } => *abs,
+		SmoothQuadratic { abs, .. } => *abs,
+		EllipticalArc { abs, .. } => *abs,
+		ClosePath { abs, .. } => *abs,
+	}
+}
+fn segment_type_id(segment: &svgtypes::PathSegment) -> &'static str {
+	use svgtypes::PathSegment::*;
+	match segment {
+		MoveTo { .. } => "m",
+		LineTo { .. } => "l",
+		HorizontalLineTo { .. } => "h",
+		VerticalLineTo { .. } => "v",
+		CurveTo { .. } => "c",
+		SmoothCurveTo { .. } => "s",
+		Quadratic { .. } => "q",
+		SmoothQuadratic { .. } => "t",
+		EllipticalArc { .. } => "a",
+		ClosePath { .. } => "z",
+	}
+}
+fn segment_id(segment: &svgtypes::PathSegment) -> String {
+	let id = segment_type_id(segment);
+	if segment_abs(segment) {
+		id.to_uppercase()
+	} else {
+		id.to_owned()
+	}
+}
+fn segment_display(segment: &svgtypes::PathSegment, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+	use svgtypes::PathSegment::*;
+	write!(f, "{}", segment_id(segment))?;
+	match segment {
+		MoveTo { x, y, .. } => write!(f, " {x} {y}")?,
+		LineTo { x, y, .. } => write!(f, " {x} {y}")?,
+		HorizontalLineTo { x, .. } => write!(f, " {x}")?,
+		VerticalLineTo { y, .. } => write!(f, " {y}")?,
+		CurveTo {
+			x1, y1, x2, y2, x, y, ..
+		} => write!(f, " {x1} {y1} {x2} {y2} {x} {y}")?,
+		SmoothCurveTo { x2, y2, x, y, .. } => write!(f, " {x2} {y2} {x} {y}")?,
+		Quadratic { x1, y1, x, y, .. } => write!(f, " {x1} {y1} {x} {y}")?,
+		SmoothQuadratic { x, y, .. } => write!(f, " {x} {y}")?,
+		EllipticalArc {
+			rx,
+			ry,
+			x_axis_rotation,
+			large_arc,
+			sweep,
+			x,
+			y,
+			..
+		} => {
+			write!(f, " {rx} {ry} {x_axis_rotation}")?;
+			write!(f, " {}", if *large_arc { 1 } else { 0 })?;
+			write!(f, " {}", if *sweep { 1 } else { 0 })?;
+			write!(f, " {x} {y}")?;
+		}
+		ClosePath { .. } => {}
+	}
+	Ok(())
+}
+
+/// An axis-aligned rectangle in screen coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+	pub min: (f64, f64),
+	pub max: (f64, f64),
+}
+
+impl Bounds {
+	pub fn point(pos: (f64, f64)) -> Self {
+		Self { min: pos, max: pos }
+	}
+
+	/// Grows the bounds to contain `pos`.
+	pub fn include(&mut self, pos: (f64, f64)) {
+		self.min = (self.min.0.min(pos.0), self.min.1.min(pos.1));
+		self.max = (self.max.0.max(pos.0), self.max.1.max(pos.1));
+	}
+
+	pub fn union(mut self, other: Self) -> Self {
+		self.include(other.min);
+		self.include(other.max);
+		self
+	}
+
+	/// The bounds grown by `amount` on every side.
+	pub fn expand(self, amount: f64) -> Self {
+		Self {
+			min: (self.min.0 - amount, self.min.1 - amount),
+			max: (self.max.0 + amount, self.max.1 + amount),
+		}
+	}
+
+	pub fn size(&self) -> (f64, f64) {
+		(self.max.0 - self.min.0, self.max.1 - self.min.1)
+	}
+}
+
+#[derive(Default)]
+pub struct ComboLinkPath(Vec<svgtypes::PathSegment>);
+impl ComboLinkPath {
+	/// The bounds which contain the whole path, including the control points of its curves
+	/// (which always contain the curve itself). None if the path has no points.
+	pub fn bounds(&self) -> Option<Bounds> {
+		let mut bounds = None::<Bounds>;
+		for segment in &self.0 {
+			let points = match *segment {
+				svgtypes::PathSegment::MoveTo { x, y, .. } | svgtypes::PathSegment::LineTo { x, y, .. } => {
+					vec![(x, y)]
+				}
+				svgtypes::PathSegment::Quadratic { x1, y1, x, y, .. } => vec![(x1, y1), (x, y)],
+				// only lines and quadratic curves are ever pushed
+				_ => continue,
+			};
+			for pos in points {
+				match &mut bounds {
+					Some(bounds) => bounds.include(pos),
+					None => bounds = Some(Bounds::point(pos)),
+				}
+			}
+		}
+		bounds
+	}
+
+	pub fn push(&mut self, pos: (f64, f64)) {
+		if self.0.is_empty() {
+			self.0.push(svgtypes::PathSegment::MoveTo {
+				abs: true,
+				x: pos.0,
+				y: pos.1,
+			});
+		} else {
+			self.0.push(svgtypes::PathSegment::LineTo {
+				abs: true,
+				x: pos.0,
+				y: pos.1,
+			});
+		}
+	}
+
+	pub fn push_curve(&mut self, a: (f64, f64), control: (f64, f64), b: (f64, f64)) {
+		self.0.push(svgtypes::PathSegment::LineTo {
+			abs: true,
+			x: a.0,
+			y: a.1,
+		});
+		self.0.push(svgtypes::PathSegment::Quadratic {
+			abs: true,
+			x1: control.0,
+			y1: control.1,
+			x: b.0,
+			y: b.1,
+		});
+	}
+}
+impl std::fmt::Display for ComboLinkPath {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		for i in 0..self.0.len() {
+			segment_display(&self.0[i], f)?;
+			if i != self.0.len() - 1 {
+				write!(f, " ")?;
+			}
+		}
+		Ok(())
+	}
+}
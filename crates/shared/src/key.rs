@@ -65,7 +65,7 @@ pub enum KeyAlias {
 	AltRight,
 	Backspace,
 	CapsLock,
-	//ContextMenu,
+	ContextMenu,
 	ControlLeft,
 	ControlRight,
 	Enter,
@@ -94,7 +94,7 @@ pub enum KeyAlias {
 	ArrowLeft,
 	ArrowRight,
 	ArrowUp,
-	//NumLock,
+	NumLock,
 	//Numpad0,
 	//Numpad1,
 	//Numpad2,
@@ -282,6 +282,55 @@ impl KeyAlias {
 		];
 		ALPHA.contains(&self)
 	}
+
+	/// True for the keys which are held alongside other keys (shift, ctrl, alt, and meta) rather than typing on their own.
+	pub fn is_modifier(&self) -> bool {
+		matches!(
+			self,
+			Self::AltLeft
+				| Self::AltRight
+				| Self::ControlLeft
+				| Self::ControlRight
+				| Self::MetaLeft
+				| Self::MetaRight
+				| Self::ShiftLeft
+				| Self::ShiftRight
+		)
+	}
+
+	/// A short label for the key, as it would be printed on a keycap.
+	/// Character keys use their character, while named keys are abbreviated.
+	pub fn legend(&self) -> String {
+		let legend = match self {
+			Self::AltLeft | Self::AltRight => "Alt",
+			Self::ControlLeft | Self::ControlRight => "Ctrl",
+			Self::MetaLeft | Self::MetaRight => "Meta",
+			Self::ShiftLeft | Self::ShiftRight => "Shift",
+			Self::Backspace => "Bksp",
+			Self::CapsLock => "Caps",
+			Self::ContextMenu => "Menu",
+			Self::Delete => "Del",
+			Self::Insert => "Ins",
+			Self::PageDown => "PgDn",
+			Self::PageUp => "PgUp",
+			Self::ArrowDown => "↓",
+			Self::ArrowLeft => "←",
+			Self::ArrowRight => "→",
+			Self::ArrowUp => "↑",
+			Self::NumLock => "Num",
+			Self::Escape => "Esc",
+			Self::PrintScreen => "PrtSc",
+			Self::ScrollLock => "ScrLk",
+			Self::MediaPlayPause => "⏯",
+			Self::MediaTrackNext => "⏭",
+			Self::MediaTrackPrevious => "⏮",
+			Self::AudioVolumeDown => "Vol-",
+			Self::AudioVolumeMute => "Mute",
+			Self::AudioVolumeUp => "Vol+",
+			_ => return self.to_string(),
+		};
+		legend.to_owned()
+	}
 }
 
 impl std::fmt::Display for KeyAlias {
@@ -344,7 +393,7 @@ impl std::fmt::Display for KeyAlias {
 				Self::AltRight => "RAlt",
 				Self::Backspace => "Backspace",
 				Self::CapsLock => "CapsLock",
-				//ContextMenu,
+				Self::ContextMenu => "ContextMenu",
 				Self::ControlLeft => "LControl",
 				Self::ControlRight => "RControl",
 				Self::Enter => "Enter",
@@ -373,7 +422,7 @@ impl std::fmt::Display for KeyAlias {
 				Self::ArrowLeft => "ArrowLeft",
 				Self::ArrowRight => "ArrowRight",
 				Self::ArrowUp => "ArrowUp",
-				//NumLock,
+				Self::NumLock => "NumLock",
 				//Numpad0,
 				//Numpad1,
 				//Numpad2,
@@ -589,7 +638,7 @@ impl std::str::FromStr for KeyAlias {
 			"RAlt" => Ok(Self::AltRight),
 			"Backspace" => Ok(Self::Backspace),
 			"CapsLock" => Ok(Self::CapsLock),
-			//ContextMenu,
+			"ContextMenu" | "Menu" => Ok(Self::ContextMenu),
 			"LControl" => Ok(Self::ControlLeft),
 			"RControl" => Ok(Self::ControlRight),
 			"Enter" => Ok(Self::Enter),
@@ -618,7 +667,7 @@ impl std::str::FromStr for KeyAlias {
 			"ArrowLeft" => Ok(Self::ArrowLeft),
 			"ArrowRight" => Ok(Self::ArrowRight),
 			"ArrowUp" => Ok(Self::ArrowUp),
-			//NumLock,
+			"NumLock" => Ok(Self::NumLock),
 			//Numpad0,
 			//Numpad1,
 			//Numpad2,
@@ -791,6 +840,11 @@ impl KeySet {
 	pub fn iter(&self) -> impl Iterator<Item = &KeyAlias> {
 		self.0.iter()
 	}
+
+	/// The keycap-style label for all of the keys in the set (e.g. "Ctrl+C").
+	pub fn legend(&self) -> String {
+		self.0.iter().map(KeyAlias::legend).join("+")
+	}
 }
 
 impl std::fmt::Display for KeySet {
@@ -803,15 +857,42 @@ impl std::str::FromStr for KeySet {
 	type Err = anyhow::Error;
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
 		let mut combo = BTreeSet::new();
-		if s == "+" {
-			combo.insert(KeyAlias::from_str(s)?);
-			return Ok(Self(combo));
-		}
-		for alias_str in s.split("+") {
+		let mut rest = s;
+		loop {
+			// A `+` where a key is expected is the plus key itself (e.g. "LControl++"), rather than a separator.
+			// Every separator must be followed by a key, so empty and trailing keys (e.g. "LControl+") are invalid.
+			let len = match rest.starts_with('+') {
+				true => 1,
+				false => rest.find('+').unwrap_or(rest.len()),
+			};
+			let alias_str = &rest[..len];
 			let alias = KeyAlias::from_str(alias_str);
 			let alias = alias.with_context(|| format!("processing combo {s:?} w/ substr {alias_str:?}"));
 			combo.insert(alias?);
+			rest = &rest[len..];
+			match rest.strip_prefix('+') {
+				Some(next) => rest = next,
+				None => break,
+			}
 		}
 		Ok(Self(combo))
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn key_set_separators() {
+		let key_count = |s: &str| s.parse::<KeySet>().ok().map(|keys| keys.0.len());
+		assert_eq!(key_count("LControl+C"), Some(2));
+		// a `+` where a key is expected is the plus key
+		assert_eq!(key_count("LControl++"), Some(2));
+		assert_eq!(key_count("+"), Some(1));
+		// a separator must be followed by a key
+		assert_eq!(key_count("LControl+"), None);
+		assert_eq!(key_count("A+"), None);
+		assert_eq!(key_count(""), None);
+	}
+}
@@ -1,13 +1,50 @@
 use crate::BoundSwitch;
 use kdlize::{AsKdl, FromKdl};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 
+/// The bindings for switches while this layer is active.
+///
+/// When resolving what a switch does, layers are checked from highest to lowest priority
+/// (the reverse of the order they are declared in the layout), and the first active layer which binds the switch wins.
+/// Switches which a layer does not bind show through to the bindings of lower layers.
+///
+/// A `bind` with no slots (e.g. `bind "l_index"` or `bind "l_index" {}`) is a blank binding.
+/// It still occupies the switch on this layer, so lower layers neither display nor trigger their bindings for it,
+/// but the switch itself shows no label and has no input of its own.
+///
+/// A `bind` can also move or resize its switch while this layer supplies the binding,
+/// with `x`, `y`, and `size` properties in the layout's units (e.g. `bind "encoder" y=-80 size=60`).
+///
+/// A layer with `passthrough=false` blocks every switch it doesn't bind, as if each had a blank binding,
+/// instead of showing the bindings of lower layers through it.
+///
+/// A layer with `suppresses_default=true` deactivates the default layer while it is active,
+/// so it can fully replace the default layer (e.g. for a gaming layer) while still showing any other active layers.
+///
+/// A `bind` shared by several layers can list the others with `also` (e.g. `also "nav" "sym"`),
+/// and is copied into each of them when the layout loads, unless that layer binds the switch itself.
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Layer {
 	bindings: BTreeMap<String, BoundSwitch>,
+	// any css color, outlining the whole overlay while this is the highest priority active layer
+	color: Option<String>,
+	// if switches which this layer doesn't bind show the bindings of lower layers (the default), or are blocked
+	passthrough: Option<bool>,
+	// while this layer is active, the default layer is treated as inactive
+	suppresses_default: bool,
 }
 
+/// The binding of switches which are blocked by a layer that doesn't pass them through to lower layers.
+static BLOCKED_BINDING: BoundSwitch = BoundSwitch {
+	slots: BTreeMap::new(),
+	x: None,
+	y: None,
+	size: None,
+	also_layers: Vec::new(),
+	shared_from: None,
+};
+
 impl Layer {
 	pub fn bindings(&self) -> &BTreeMap<String, BoundSwitch> {
 		&self.bindings
@@ -16,26 +53,101 @@ impl Layer {
 	pub fn get_binding(&self, switch: impl AsRef<str>) -> Option<&BoundSwitch> {
 		self.bindings.get(switch.as_ref())
 	}
+
+	/// The binding this layer supplies for a switch while it is active, which is a blank binding for switches it
+	/// doesn't bind when the layer doesn't pass them through. None if lower layers supply the switch's binding.
+	pub fn resolve_binding(&self, switch: impl AsRef<str>) -> Option<&BoundSwitch> {
+		match (self.get_binding(switch), self.passthrough()) {
+			(Some(binding), _) => Some(binding),
+			(None, true) => None,
+			(None, false) => Some(&BLOCKED_BINDING),
+		}
+	}
+
+	pub fn passthrough(&self) -> bool {
+		self.passthrough.unwrap_or(true)
+	}
+
+	pub fn suppresses_default(&self) -> bool {
+		self.suppresses_default
+	}
+
+	pub fn color(&self) -> Option<&String> {
+		self.color.as_ref()
+	}
+}
+
+/// Returns true if the layer is one of the active layers, treating the default layer as inactive
+/// while any of the `suppressors` (the layers which suppress the default layer) is active.
+pub fn is_layer_active(
+	layer_id: &String,
+	default_layer: &String,
+	active_layers: &HashSet<String>,
+	suppressors: impl IntoIterator<Item = impl AsRef<str>>,
+) -> bool {
+	if !active_layers.contains(layer_id) {
+		return false;
+	}
+	*layer_id != *default_layer || !suppressors.into_iter().any(|id| active_layers.contains(id.as_ref()))
+}
+
+/// Copies bindings which list other layers with `also` into those layers, unless a layer binds the switch itself.
+/// Layers which don't exist are skipped, and reported by config validation.
+pub(crate) fn expand_shared_bindings(layers: &mut BTreeMap<String, Layer>) {
+	let mut shared_bindings = Vec::new();
+	for (layer_id, layer) in layers.iter() {
+		for (switch_id, binding) in &layer.bindings {
+			for target in &binding.also_layers {
+				let binding = BoundSwitch {
+					also_layers: Vec::new(),
+					shared_from: Some(layer_id.clone()),
+					..binding.clone()
+				};
+				shared_bindings.push((target.clone(), switch_id.clone(), binding));
+			}
+		}
+	}
+	for (target, switch_id, binding) in shared_bindings {
+		let Some(layer) = layers.get_mut(&target) else {
+			continue;
+		};
+		layer.bindings.entry(switch_id).or_insert(binding);
+	}
 }
 
 impl FromKdl<()> for Layer {
 	type Error = anyhow::Error;
 
 	fn from_kdl<'doc>(node: &mut kdlize::NodeReader<'doc, ()>) -> Result<Self, Self::Error> {
+		let color = node.get_str_opt("color")?.map(str::to_owned);
+		let passthrough = node.get_bool_opt("passthrough")?;
+		let suppresses_default = node.get_bool_opt("suppresses_default")?.unwrap_or_default();
 		let mut bindings = BTreeMap::new();
 		for mut node in node.query_all("scope() > bind")? {
 			let switch_id = node.next_str_req()?.to_owned();
 			let binding = BoundSwitch::from_kdl(&mut node)?;
 			bindings.insert(switch_id, binding);
 		}
-		Ok(Self { bindings })
+		Ok(Self {
+			bindings,
+			color,
+			passthrough,
+			suppresses_default,
+		})
 	}
 }
 
 impl AsKdl for Layer {
 	fn as_kdl(&self) -> kdlize::NodeBuilder {
 		let mut node = kdlize::NodeBuilder::default();
-		for (switch_id, binding) in &self.bindings {
+		node.entry(("color", self.color.clone()));
+		node.entry(("passthrough", self.passthrough));
+		if self.suppresses_default {
+			node.entry(("suppresses_default", true));
+		}
+		// shared copies are written only by the layer they are authored on, so edits to it keep reaching every layer
+		let bindings = self.bindings.iter();
+		for (switch_id, binding) in bindings.filter(|(_, binding)| binding.shared_from.is_none()) {
 			let node_binding = kdlize::NodeBuilder::default()
 				.with_entry(switch_id.as_str())
 				.with(binding.as_kdl());
@@ -44,3 +156,80 @@ impl AsKdl for Layer {
 		node
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{layout::test::parse_layout, SwitchSlot};
+
+	#[test]
+	fn layer_switch_override() {
+		let layout = parse_layout(
+			r#"
+layout {
+	default_layer "base"
+	switch "encoder" 100 50
+	layer "base" {
+		bind "encoder" {
+			slot "Tap" "MediaPlayPause"
+		}
+	}
+	layer "media" {
+		bind "encoder" y=-80 size=60 {
+			slot "Tap" "AudioVolumeMute"
+		}
+	}
+}
+"#,
+		);
+		let switch = &layout.switches()["encoder"];
+		let bound_switch = |layer: &str| layout.get_layer(layer).unwrap().get_binding("encoder").unwrap();
+		assert_eq!(bound_switch("base").override_switch(switch), *switch);
+		let media_switch = bound_switch("media").override_switch(switch);
+		assert_eq!(media_switch.pos, (100.0, -80.0));
+		assert_eq!(media_switch.size(), 60.0);
+	}
+
+	#[test]
+	fn shared_bindings() {
+		let layout = parse_layout(
+			r#"
+layout {
+	default_layer "base"
+	switch "l_index" 0 0
+	switch "r_index" 50 0
+	layer "base" {
+		bind "r_index" {
+			slot "Tap" "J"
+		}
+	}
+	layer "nav" {
+		bind "l_index" {
+			also "sym" "num"
+			slot "Tap" "Escape"
+		}
+	}
+	layer "sym" {
+	}
+	layer "num" {
+		bind "l_index" {
+			slot "Tap" "0"
+		}
+	}
+}
+"#,
+		);
+		let tap = |layer_id: &str| {
+			let layer = layout.get_layer(layer_id).expect("missing layer");
+			let bindings = layer.get_binding("l_index")?;
+			Some(bindings.slots[&SwitchSlot::Tap].input.to_string())
+		};
+		assert_eq!(tap("base"), None);
+		assert_eq!(tap("sym"), Some("Escape".to_owned()));
+		// a layer's own binding wins over a shared one
+		assert_eq!(tap("num"), Some("0".to_owned()));
+		// the shared binding is saved only on its own layer, not copied into the others
+		let serialized = layout.as_kdl().build("layout").to_string();
+		assert_eq!(serialized.matches("Escape").count(), 1);
+	}
+}
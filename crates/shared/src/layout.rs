@@ -1,7 +1,12 @@
-use crate::{Combo, Layer, Switch};
+use crate::{
+	geometry::{Origin, Split, Units, DEFAULT_UNIT_PX, KEY_UNIT_GAP_PX, STACK_OFFSET_PX},
+	is_layer_active,
+	layer::expand_shared_bindings,
+	Combo, DeadKey, Layer, LayoutParseError, Switch, SwitchShape,
+};
 use kdlize::{ext::DocumentExt, AsKdl, FromKdl};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Layout {
@@ -10,9 +15,108 @@ pub struct Layout {
 	default_layer: String,
 	layer_order: Vec<String>,
 	layers: BTreeMap<String, Layer>,
+	// milliseconds between presses of the same switch for them to count as repeated taps
+	tap_window: Option<u64>,
+	// minimum milliseconds a switch is shown as pressed, so quick taps are still visible
+	min_press_ms: Option<u64>,
+	// extra milliseconds a press shorter than `min_press_ms` stays shown, without delaying the release of longer holds
+	hold_extension_ms: Option<u64>,
+	// when a non-default layer is active, switches bound by lower layers are dimmed
+	dim_inactive: bool,
+	// minimum milliseconds a layer stays shown once activated, to avoid flickering on quick taps
+	layer_min_duration: Option<u64>,
+	// milliseconds after a combo is pressed or released that the individual bindings of its linked switches
+	// can't trigger, so releasing the chord one key at a time doesn't flash those switches
+	combo_lockout_ms: Option<u64>,
+	// milliseconds without any key events after which held keys are assumed stuck and released.
+	// Only keys autorepeat while held (modifiers don't on every platform), so this is off unless set.
+	stuck_input_timeout_ms: Option<u64>,
+	// the coordinate convention for switch and combo positions
+	origin: Origin,
+	key_colors: KeyColors,
+	// the shape of all switches which don't specify their own
+	switch_shape: SwitchShape,
+	// shows a large readout of the most recently pressed key in the center of the overlay
+	show_last_key: bool,
+	// milliseconds the last key readout is shown before it has faded out
+	last_key_duration: Option<u64>,
+	// switches with no binding on any active layer are shown as faint placeholders instead of hidden
+	show_unbound: bool,
+	// shows a status strip of held modifiers and toggled locks, independent of the switches in the layout
+	show_modifiers: bool,
+	// switches with both a tap and a hold binding show a divider line between them
+	show_slot_divider: bool,
+	// shows the mascot image, which is fetched from github (so it is off by default for offline/private use)
+	show_mascot: bool,
+	// shows the crosshair guidelines through the center of the overlay (shown unless disabled)
+	show_guidelines: Option<bool>,
+	// pressed switches glow brighter the faster keys are being typed, with a words-per-minute readout
+	show_typing_speed: bool,
+	// long text labels wrap onto a second line and shrink to fit, instead of being clipped by small switches
+	wrap_labels: bool,
+	// the opacity the overlay fades to after no input for `idle_dim_ms`, returning to full opacity on the next input
+	idle_dim_opacity: Option<f64>,
+	idle_dim_ms: Option<u64>,
+	// keys which compose a character with the next key, so the composed glyph is shown instead of the raw key
+	dead_keys: Vec<DeadKey>,
+	// the unit switch positions and sizes are authored in
+	units: Units,
+	// pixels per key unit, when authored in key units
+	unit_px: Option<f32>,
+	// pixels between, and degrees of rotation for, the left and right halves of a split keyboard
+	split_gap: f64,
+	split_angle: f64,
 }
 
+/// Colors for bindings based on their category, applied to all bindings of that category.
+/// Each color is any css color value.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyColors {
+	/// The color of bindings which change the active layer.
+	pub layer: Option<String>,
+	/// The color of all other bindings.
+	pub action: Option<String>,
+}
+
+impl FromKdl<()> for KeyColors {
+	type Error = anyhow::Error;
+
+	fn from_kdl<'doc>(node: &mut kdlize::NodeReader<'doc, ()>) -> Result<Self, Self::Error> {
+		let layer = node.get_str_opt("layer")?.map(str::to_owned);
+		let action = node.get_str_opt("action")?.map(str::to_owned);
+		Ok(Self { layer, action })
+	}
+}
+
+impl AsKdl for KeyColors {
+	fn as_kdl(&self) -> kdlize::NodeBuilder {
+		let mut node = kdlize::NodeBuilder::default();
+		node.entry(("layer", self.layer.clone()));
+		node.entry(("action", self.action.clone()));
+		node
+	}
+}
+
+/// The `layer_order` of a layout doesn't list each of its layers exactly once,
+/// so layers missing from the order would never be drawn.
+#[derive(thiserror::Error, Clone, Debug, PartialEq)]
+#[error("Layer order {order:?} does not match the layout's layers {layers:?}")]
+pub struct LayerOrderMismatch {
+	pub order: Vec<String>,
+	pub layers: Vec<String>,
+}
+
+static DEFAULT_TAP_WINDOW_MS: u64 = 250;
+static DEFAULT_MIN_PRESS_MS: u64 = 100;
+static DEFAULT_LAST_KEY_DURATION_MS: u64 = 1500;
+static DEFAULT_IDLE_DIM_MS: u64 = 5000;
+
 impl Layout {
+	/// Parses a layout node, with errors that can be matched on by what was invalid.
+	pub fn parse(node: &mut kdlize::NodeReader<'_, ()>) -> Result<Self, LayoutParseError> {
+		Ok(Self::from_kdl(node)?)
+	}
+
 	pub fn default_layer(&self) -> &String {
 		&self.default_layer
 	}
@@ -36,6 +140,175 @@ impl Layout {
 	pub fn layers(&self) -> &BTreeMap<String, Layer> {
 		&self.layers
 	}
+
+	/// Returns true if the layer is one of the active layers, treating the default layer as inactive
+	/// while any active layer suppresses it.
+	pub fn is_layer_active(&self, layer_id: &String, active_layers: &HashSet<String>) -> bool {
+		let suppressors = self.layers.iter().filter(|(_, layer)| layer.suppresses_default());
+		let suppressors = suppressors.map(|(id, _)| id);
+		is_layer_active(layer_id, &self.default_layer, active_layers, suppressors)
+	}
+
+	/// Checks that `layer_order` lists every layer exactly once.
+	/// Parsed layouts always do, but a layout deserialized from elsewhere (like an ipc event) may not.
+	pub fn validate_layer_order(&self) -> Result<(), LayerOrderMismatch> {
+		let mut order = self.layer_order.iter().collect::<Vec<_>>();
+		order.sort();
+		if order.into_iter().eq(self.layers.keys()) {
+			return Ok(());
+		}
+		Err(LayerOrderMismatch {
+			order: self.layer_order.clone(),
+			layers: self.layers.keys().cloned().collect(),
+		})
+	}
+
+	pub fn tap_window(&self) -> std::time::Duration {
+		std::time::Duration::from_millis(self.tap_window.unwrap_or(DEFAULT_TAP_WINDOW_MS))
+	}
+
+	pub fn min_press_duration(&self) -> std::time::Duration {
+		std::time::Duration::from_millis(self.min_press_ms.unwrap_or(DEFAULT_MIN_PRESS_MS))
+	}
+
+	pub fn hold_extension(&self) -> std::time::Duration {
+		std::time::Duration::from_millis(self.hold_extension_ms.unwrap_or_default())
+	}
+
+	pub fn dim_inactive(&self) -> bool {
+		self.dim_inactive
+	}
+
+	pub fn layer_min_duration(&self) -> Option<std::time::Duration> {
+		self.layer_min_duration.map(std::time::Duration::from_millis)
+	}
+
+	/// How long the switches linked to a combo are locked out after the combo is pressed or released, if at all.
+	pub fn combo_lockout(&self) -> Option<std::time::Duration> {
+		let lockout_ms = self.combo_lockout_ms.filter(|ms| *ms > 0);
+		lockout_ms.map(std::time::Duration::from_millis)
+	}
+
+	/// How long without key events before held keys are released as stuck, if they ever are.
+	pub fn stuck_input_timeout(&self) -> Option<std::time::Duration> {
+		let timeout_ms = self.stuck_input_timeout_ms.filter(|ms| *ms > 0);
+		timeout_ms.map(std::time::Duration::from_millis)
+	}
+
+	pub fn origin(&self) -> Origin {
+		self.origin
+	}
+
+	pub fn key_colors(&self) -> &KeyColors {
+		&self.key_colors
+	}
+
+	/// The shape to render a switch with, which is the switch's own shape if it has one.
+	pub fn switch_shape(&self, switch: &Switch) -> SwitchShape {
+		switch.shape.unwrap_or(self.switch_shape)
+	}
+
+	pub fn show_last_key(&self) -> bool {
+		self.show_last_key
+	}
+
+	pub fn last_key_duration(&self) -> std::time::Duration {
+		std::time::Duration::from_millis(self.last_key_duration.unwrap_or(DEFAULT_LAST_KEY_DURATION_MS))
+	}
+
+	pub fn show_unbound(&self) -> bool {
+		self.show_unbound
+	}
+
+	pub fn show_modifiers(&self) -> bool {
+		self.show_modifiers
+	}
+
+	pub fn show_slot_divider(&self) -> bool {
+		self.show_slot_divider
+	}
+
+	pub fn show_mascot(&self) -> bool {
+		self.show_mascot
+	}
+
+	pub fn show_guidelines(&self) -> bool {
+		self.show_guidelines.unwrap_or(true)
+	}
+
+	pub fn show_typing_speed(&self) -> bool {
+		self.show_typing_speed
+	}
+
+	pub fn wrap_labels(&self) -> bool {
+		self.wrap_labels
+	}
+
+	/// The opacity the overlay dims to when idle, and how long without input before it dims.
+	/// None when the layout doesn't set `idle_dim_opacity`, so the overlay is never dimmed.
+	pub fn idle_dim(&self) -> Option<(f64, std::time::Duration)> {
+		let opacity = self.idle_dim_opacity?.clamp(0.0, 1.0);
+		let duration = std::time::Duration::from_millis(self.idle_dim_ms.unwrap_or(DEFAULT_IDLE_DIM_MS));
+		Some((opacity, duration))
+	}
+
+	pub fn dead_keys(&self) -> &Vec<DeadKey> {
+		&self.dead_keys
+	}
+
+	pub fn units(&self) -> Units {
+		self.units
+	}
+
+	pub fn unit_px(&self) -> f32 {
+		self.unit_px.unwrap_or(DEFAULT_UNIT_PX)
+	}
+
+	pub fn split(&self) -> Split {
+		Split {
+			gap: self.split_gap,
+			angle: self.split_angle,
+		}
+	}
+
+	/// The switch with its position and size converted from the layout's units into pixels, for rendering.
+	pub fn switch_px(&self, switch: &Switch) -> Switch {
+		match self.units {
+			Units::Px => switch.clone(),
+			Units::U => {
+				let unit_px = self.unit_px();
+				// a switch's size in key units includes the gap to its neighbors, so it is removed from the keycap
+				let size = switch.size.unwrap_or(1.0) * unit_px - KEY_UNIT_GAP_PX;
+				Switch {
+					pos: (switch.pos.0 * unit_px, switch.pos.1 * unit_px),
+					size: Some(size),
+					..switch.clone()
+				}
+			}
+		}
+	}
+
+	/// The switch as rendered, in pixels (see `switch_px`) and offset from the other switches in its stack.
+	/// Stacked switches are spread evenly around their shared position, in the order of their ids.
+	pub fn switch_placement(&self, switch_id: &str, switch: &Switch) -> Switch {
+		let mut switch = self.switch_px(switch);
+		let Some(stack) = &switch.stack else {
+			return switch;
+		};
+		let mut stacked_ids = Vec::new();
+		for (id, other) in &self.switches {
+			if other.stack.as_ref() == Some(stack) {
+				stacked_ids.push(id);
+			}
+		}
+		let count = stacked_ids.len();
+		let Some(index) = stacked_ids.iter().position(|id| *id == switch_id) else {
+			return switch;
+		};
+		let offset = (index as f32 - (count - 1) as f32 * 0.5) * STACK_OFFSET_PX;
+		switch.pos = (switch.pos.0 + offset, switch.pos.1 + offset);
+		switch
+	}
 }
 
 impl FromKdl<()> for Layout {
@@ -61,6 +334,46 @@ impl FromKdl<()> for Layout {
 			layer_order.push(name.clone());
 			layers.insert(name, layer);
 		}
+		expand_shared_bindings(&mut layers);
+
+		let tap_window = node.query_i64_opt("scope() > tap_window", 0)?.map(|ms| ms as u64);
+		let min_press_ms = node.query_i64_opt("scope() > min_press_ms", 0)?.map(|ms| ms as u64);
+		let hold_extension_ms = node
+			.query_i64_opt("scope() > hold_extension_ms", 0)?
+			.map(|ms| ms as u64);
+		let dim_inactive = node.query_bool_opt("scope() > dim_inactive", 0)?.unwrap_or_default();
+		let layer_min_duration = node
+			.query_i64_opt("scope() > layer_min_duration", 0)?
+			.map(|ms| ms as u64);
+		let combo_lockout_ms = node.query_i64_opt("scope() > combo_lockout_ms", 0)?.map(|ms| ms as u64);
+		let stuck_input_timeout_ms = node
+			.query_i64_opt("scope() > stuck_input_timeout_ms", 0)?
+			.map(|ms| ms as u64);
+		let origin = node.query_str_opt_t("scope() > origin", 0)?.unwrap_or_default();
+		let key_colors = node.query_opt_t("scope() > key_colors")?.unwrap_or_default();
+		let switch_shape = node.query_str_opt_t("scope() > switch_shape", 0)?.unwrap_or_default();
+		let show_last_key = node.query_bool_opt("scope() > show_last_key", 0)?.unwrap_or_default();
+		let last_key_duration = node
+			.query_i64_opt("scope() > last_key_duration", 0)?
+			.map(|ms| ms as u64);
+		let show_unbound = node.query_bool_opt("scope() > show_unbound", 0)?.unwrap_or_default();
+		let show_modifiers = node.query_bool_opt("scope() > show_modifiers", 0)?.unwrap_or_default();
+		let show_slot_divider = node
+			.query_bool_opt("scope() > show_slot_divider", 0)?
+			.unwrap_or_default();
+		let show_mascot = node.query_bool_opt("scope() > show_mascot", 0)?.unwrap_or_default();
+		let show_guidelines = node.query_bool_opt("scope() > show_guidelines", 0)?;
+		let show_typing_speed = node
+			.query_bool_opt("scope() > show_typing_speed", 0)?
+			.unwrap_or_default();
+		let wrap_labels = node.query_bool_opt("scope() > wrap_labels", 0)?.unwrap_or_default();
+		let idle_dim_opacity = node.query_f64_opt("scope() > idle_dim_opacity", 0)?;
+		let idle_dim_ms = node.query_i64_opt("scope() > idle_dim_ms", 0)?.map(|ms| ms as u64);
+		let dead_keys = node.query_all_t("scope() > dead_key")?;
+		let units = node.query_str_opt_t("scope() > units", 0)?.unwrap_or_default();
+		let unit_px = node.query_f64_opt("scope() > unit_px", 0)?.map(|px| px as f32);
+		let split_gap = node.query_f64_opt("scope() > split_gap", 0)?.unwrap_or_default();
+		let split_angle = node.query_f64_opt("scope() > split_angle", 0)?.unwrap_or_default();
 
 		Ok(Self {
 			switches,
@@ -68,6 +381,32 @@ impl FromKdl<()> for Layout {
 			default_layer,
 			layer_order,
 			layers,
+			tap_window,
+			min_press_ms,
+			hold_extension_ms,
+			dim_inactive,
+			layer_min_duration,
+			combo_lockout_ms,
+			stuck_input_timeout_ms,
+			origin,
+			key_colors,
+			switch_shape,
+			show_last_key,
+			last_key_duration,
+			show_unbound,
+			show_modifiers,
+			show_slot_divider,
+			show_mascot,
+			show_guidelines,
+			show_typing_speed,
+			wrap_labels,
+			idle_dim_opacity,
+			idle_dim_ms,
+			dead_keys,
+			units,
+			unit_px,
+			split_gap,
+			split_angle,
 		})
 	}
 }
@@ -76,10 +415,86 @@ impl AsKdl for Layout {
 	fn as_kdl(&self) -> kdlize::NodeBuilder {
 		let mut node = kdlize::NodeBuilder::default();
 		node.child(("default_layer", &self.default_layer));
+		if let Some(tap_window) = &self.tap_window {
+			node.child(("tap_window", &(*tap_window as i64)));
+		}
+		if let Some(min_press_ms) = &self.min_press_ms {
+			node.child(("min_press_ms", &(*min_press_ms as i64)));
+		}
+		if let Some(hold_extension_ms) = &self.hold_extension_ms {
+			node.child(("hold_extension_ms", &(*hold_extension_ms as i64)));
+		}
+		if self.dim_inactive {
+			node.child(("dim_inactive", &self.dim_inactive));
+		}
+		if let Some(layer_min_duration) = &self.layer_min_duration {
+			node.child(("layer_min_duration", &(*layer_min_duration as i64)));
+		}
+		if let Some(combo_lockout_ms) = &self.combo_lockout_ms {
+			node.child(("combo_lockout_ms", &(*combo_lockout_ms as i64)));
+		}
+		if let Some(stuck_input_timeout_ms) = &self.stuck_input_timeout_ms {
+			node.child(("stuck_input_timeout_ms", &(*stuck_input_timeout_ms as i64)));
+		}
+		if self.origin != Origin::default() {
+			node.child(("origin", &self.origin.to_string()));
+		}
+		if self.key_colors != KeyColors::default() {
+			node.child(("key_colors", &self.key_colors));
+		}
+		if self.switch_shape != SwitchShape::default() {
+			node.child(("switch_shape", &self.switch_shape.to_string()));
+		}
+		if self.show_last_key {
+			node.child(("show_last_key", &self.show_last_key));
+		}
+		if let Some(last_key_duration) = &self.last_key_duration {
+			node.child(("last_key_duration", &(*last_key_duration as i64)));
+		}
+		if self.show_unbound {
+			node.child(("show_unbound", &self.show_unbound));
+		}
+		if self.show_modifiers {
+			node.child(("show_modifiers", &self.show_modifiers));
+		}
+		if self.show_slot_divider {
+			node.child(("show_slot_divider", &self.show_slot_divider));
+		}
+		if self.show_mascot {
+			node.child(("show_mascot", &self.show_mascot));
+		}
+		if let Some(show_guidelines) = &self.show_guidelines {
+			node.child(("show_guidelines", show_guidelines));
+		}
+		if self.show_typing_speed {
+			node.child(("show_typing_speed", &self.show_typing_speed));
+		}
+		if self.wrap_labels {
+			node.child(("wrap_labels", &self.wrap_labels));
+		}
+		if let Some(idle_dim_opacity) = &self.idle_dim_opacity {
+			node.child(("idle_dim_opacity", idle_dim_opacity));
+		}
+		if let Some(idle_dim_ms) = &self.idle_dim_ms {
+			node.child(("idle_dim_ms", &(*idle_dim_ms as i64)));
+		}
+		if self.units != Units::default() {
+			node.child(("units", &self.units.to_string()));
+		}
+		if let Some(unit_px) = &self.unit_px {
+			node.child(("unit_px", &(*unit_px as f64)));
+		}
+		if self.split_gap != 0.0 {
+			node.child(("split_gap", &self.split_gap));
+		}
+		if self.split_angle != 0.0 {
+			node.child(("split_angle", &self.split_angle));
+		}
 		for (name, switch) in &self.switches {
 			node.child(("switch", &(name, switch)));
 		}
 		node.children(("combo", &self.combos));
+		node.children(("dead_key", &self.dead_keys));
 		for name in &self.layer_order {
 			let Some(layer) = self.layers.get(name) else { continue };
 			node.child(("layer", &(name, layer)));
@@ -87,3 +502,135 @@ impl AsKdl for Layout {
 		node
 	}
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+	use super::*;
+
+	/// Parses the `layout` node of a kdl document.
+	pub(crate) fn read_layout(kdl: &str) -> Result<Layout, LayoutParseError> {
+		let doc = kdl.parse::<kdl::KdlDocument>().expect("failed to parse kdl");
+		let node = doc.get("layout").expect("missing layout node");
+		Layout::parse(&mut kdlize::NodeReader::new_root(node, ()))
+	}
+
+	/// Parses the `layout` node of a kdl document, asserting that it is unchanged by saving and reloading it.
+	pub(crate) fn parse_layout(kdl: &str) -> Layout {
+		let layout = read_layout(kdl).expect("failed to parse layout");
+		let serialized = layout.as_kdl().build("layout").to_string();
+		assert_eq!(read_layout(&serialized).ok(), Some(layout.clone()), "{serialized}");
+		layout
+	}
+
+	#[test]
+	fn key_units() {
+		let layout = parse_layout(
+			r#"
+layout {
+	default_layer "base"
+	units "u"
+	unit_px 60
+	switch "space" 0.5 -2 size=2
+}
+"#,
+		);
+		let switch = layout.switch_px(&layout.switches()["space"]);
+		assert_eq!(switch.pos, (30.0, -120.0));
+		assert_eq!(switch.size(), 2.0 * 60.0 - KEY_UNIT_GAP_PX);
+	}
+
+	#[test]
+	fn press_timing() {
+		let layout = Layout::default();
+		assert_eq!(layout.min_press_duration(), std::time::Duration::from_millis(100));
+		assert_eq!(layout.hold_extension(), std::time::Duration::ZERO);
+		let layout = parse_layout(
+			r#"
+layout {
+	default_layer "base"
+	min_press_ms 80
+	hold_extension_ms 150
+}
+"#,
+		);
+		assert_eq!(layout.min_press_duration(), std::time::Duration::from_millis(80));
+		assert_eq!(layout.hold_extension(), std::time::Duration::from_millis(150));
+	}
+
+	#[test]
+	fn idle_dim() {
+		assert_eq!(Layout::default().idle_dim(), None);
+		let layout = parse_layout(
+			r#"
+layout {
+	default_layer "base"
+	idle_dim_opacity 0.25
+	idle_dim_ms 3000
+}
+"#,
+		);
+		assert_eq!(layout.idle_dim(), Some((0.25, std::time::Duration::from_millis(3000))));
+	}
+
+	#[test]
+	fn stuck_input_timeout() {
+		assert_eq!(Layout::default().stuck_input_timeout(), None);
+		let layout = parse_layout(
+			r#"
+layout {
+	default_layer "base"
+	stuck_input_timeout_ms 5000
+}
+"#,
+		);
+		assert_eq!(layout.stuck_input_timeout(), Some(std::time::Duration::from_secs(5)));
+	}
+
+	#[test]
+	fn typed_parse_errors() {
+		let kdl = r#"
+layout {
+	default_layer "base"
+	units "px"
+	switch_shape "square"
+	switch "a" 0 0
+	layer "base" {
+		bind "a" {
+			slot "Tap" "F"
+		}
+	}
+}
+"#;
+		assert!(read_layout(kdl).is_ok());
+		let key_alias = read_layout(&kdl.replace(r#""F""#, r#""NotAKey""#));
+		assert!(matches!(key_alias, Err(LayoutParseError::KeyAlias(_))), "{key_alias:?}");
+		let slot = read_layout(&kdl.replace(r#""Tap""#, r#""Squeeze""#));
+		assert!(matches!(slot, Err(LayoutParseError::Slot(_))), "{slot:?}");
+		let shape = read_layout(&kdl.replace(r#""square""#, r#""hexagon""#));
+		assert!(matches!(shape, Err(LayoutParseError::SwitchShape(_))), "{shape:?}");
+		let units = read_layout(&kdl.replace(r#""px""#, r#""cm""#));
+		assert!(matches!(units, Err(LayoutParseError::Units(_))), "{units:?}");
+		// documents missing required nodes fail without a more specific error
+		let missing = read_layout(&kdl.replace(r#"default_layer "base""#, ""));
+		assert!(matches!(missing, Err(LayoutParseError::Kdl(_))), "{missing:?}");
+	}
+
+	#[test]
+	fn stacked_switches() {
+		let layout = parse_layout(
+			r#"
+layout {
+	default_layer "base"
+	switch "encoder_ccw" 100 0 legend="↺" stack="encoder"
+	switch "encoder_cw" 100 0 legend="↻" stack="encoder"
+	switch "solo" 0 0
+}
+"#,
+		);
+		let placement = |id: &str| layout.switch_placement(id, &layout.switches()[id]).pos;
+		let offset = STACK_OFFSET_PX * 0.5;
+		assert_eq!(placement("encoder_ccw"), (100.0 - offset, -offset));
+		assert_eq!(placement("encoder_cw"), (100.0 + offset, offset));
+		assert_eq!(placement("solo"), (0.0, 0.0));
+	}
+}
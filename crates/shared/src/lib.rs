@@ -4,6 +4,11 @@ mod binding;
 pub use binding::*;
 mod combo;
 pub use combo::*;
+mod dead_key;
+pub use dead_key::*;
+mod error;
+pub use error::*;
+pub mod geometry;
 mod key;
 pub use key::*;
 mod layer;
@@ -13,6 +18,9 @@ pub use layout::*;
 mod switch;
 pub use switch::*;
 
+/// The label of the opaque window which mirrors the overlay, for capture setups which can't capture transparent windows.
+pub static MIRROR_WINDOW_LABEL: &str = "mirror";
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct LogRecord {
 	pub level: ::log::Level,
@@ -22,10 +30,53 @@ pub struct LogRecord {
 	pub args: String,
 }
 
+/// Runtime toggles for optional overlay visuals, controlled via the system tray.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DisplayFlags {
+	pub design_grid: bool,
+	// shows the bindings from every layer on each switch, instead of only the active binding
+	pub all_layers: bool,
+}
+
+/// Which modifiers are held and which locks are toggled on, shown as status indicators regardless of the layout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModifierState {
+	pub shift: bool,
+	pub ctrl: bool,
+	pub alt: bool,
+	pub meta: bool,
+	pub caps_lock: bool,
+	pub num_lock: bool,
+	pub scroll_lock: bool,
+}
+
+/// A request to highlight a switch independently of real input (e.g. to show which key to press next).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SwitchHighlight {
+	pub switch_id: String,
+	pub duration_ms: u64,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum InputUpdate {
 	LayerActivate(String),
 	LayerDeactivate(String),
 	SwitchPressed(String, Option<SwitchSlot>),
 	SwitchReleased(String),
+	// A switch which must be held to activate has started being held, and will be pressed once held long enough
+	HoldStarted(String),
+	// A switch which was being held to activate was released before it activated
+	HoldCancelled(String),
+	// Multiple updates which occurred from the same input event, in the order they were applied
+	Batch(Vec<InputUpdate>),
+}
+
+impl InputUpdate {
+	/// Expands any batched updates into a flat list of individual updates, preserving their order.
+	pub fn flatten(self) -> Vec<InputUpdate> {
+		match self {
+			Self::Batch(updates) => updates.into_iter().flat_map(Self::flatten).collect(),
+			update => vec![update],
+		}
+	}
 }
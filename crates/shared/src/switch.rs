@@ -36,15 +36,27 @@ impl std::fmt::Display for SwitchSlot {
 	}
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Switch {
 	pub pos: (f32, f32),
 	pub side: Option<Side>,
+	// the physical legend of the keycap, shown behind whatever binding is active
+	pub legend: Option<String>,
+	// overrides the layout's switch shape for this switch
+	pub shape: Option<SwitchShape>,
+	// the width and height of the switch, in the layout's units
+	pub size: Option<f32>,
+	// switches in the same stack share a position (such as each direction of a rotary encoder),
+	// and are drawn slightly offset from each other
+	pub stack: Option<String>,
 }
 
+static DEFAULT_SWITCH_SIZE_PX: f32 = 45.0;
+
 impl Switch {
+	/// The width and height of the switch in pixels, for a switch whose position is in pixels (see `Layout::switch_px`).
 	pub fn size(&self) -> f32 {
-		45f32
+		self.size.unwrap_or(DEFAULT_SWITCH_SIZE_PX)
 	}
 }
 
@@ -55,7 +67,18 @@ impl kdlize::FromKdl<()> for Switch {
 		let x = node.next_f64_req()? as f32;
 		let y = node.next_f64_req()? as f32;
 		let side = node.get_str_opt_t::<Side>("side")?;
-		Ok(Self { pos: (x, y), side })
+		let legend = node.get_str_opt("legend")?.map(str::to_owned);
+		let shape = node.get_str_opt_t::<SwitchShape>("shape")?;
+		let size = node.get_f64_opt("size")?.map(|size| size as f32);
+		let stack = node.get_str_opt("stack")?.map(str::to_owned);
+		Ok(Self {
+			pos: (x, y),
+			side,
+			legend,
+			shape,
+			size,
+			stack,
+		})
 	}
 }
 
@@ -67,10 +90,65 @@ impl AsKdl for Switch {
 		if let Some(side) = self.side {
 			node.entry(("side", side.to_string()));
 		}
+		node.entry(("legend", self.legend.clone()));
+		node.entry(("shape", self.shape.as_ref().map(SwitchShape::to_string)));
+		node.entry(("size", self.size.map(|size| size as f64)));
+		node.entry(("stack", self.stack.clone()));
 		node
 	}
 }
 
+/// The outline of a switch when rendered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum SwitchShape {
+	Square,
+	#[default]
+	Rounded,
+	Circle,
+}
+
+impl SwitchShape {
+	/// The border radius of a switch with this shape, in pixels.
+	pub fn border_radius(&self, size: f32) -> f32 {
+		match self {
+			Self::Square => 0.0,
+			Self::Rounded => 10.0,
+			Self::Circle => size * 0.5,
+		}
+	}
+}
+
+impl std::fmt::Display for SwitchShape {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Self::Square => "square",
+				Self::Rounded => "rounded",
+				Self::Circle => "circle",
+			}
+		)
+	}
+}
+
+impl std::str::FromStr for SwitchShape {
+	type Err = InvalidSwitchShape;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"square" => Ok(Self::Square),
+			"rounded" => Ok(Self::Rounded),
+			"circle" => Ok(Self::Circle),
+			_ => Err(InvalidSwitchShape(s.to_owned())),
+		}
+	}
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid SwitchShape {0}, expecting \"square\", \"rounded\", or \"circle\"")]
+pub struct InvalidSwitchShape(String);
+
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Side {
 	Left,